@@ -4,6 +4,26 @@ mod app;
 mod graph;
 #[path = "modules/console.rs"]
 mod console;
+#[path = "modules/local_search.rs"]
+mod local_search;
+#[path = "modules/exact.rs"]
+mod exact;
+#[path = "modules/vehicle_routing.rs"]
+mod vehicle_routing;
+#[path = "modules/time_windows.rs"]
+mod time_windows;
+#[path = "modules/selective_routing.rs"]
+mod selective_routing;
+#[path = "modules/clusters.rs"]
+mod clusters;
+#[path = "modules/precedence.rs"]
+mod precedence;
+#[path = "modules/dynamic.rs"]
+mod dynamic;
+#[path = "modules/view_3d.rs"]
+mod view_3d;
+#[path = "modules/tsplib.rs"]
+mod tsplib;
 
 use bevy::{prelude::*, window::PresentMode};
 use bevy_prototype_debug_lines::*;
@@ -17,30 +37,159 @@ fn main() {
         .insert_resource(graph::EditMode {
             activate: true,
         })
+        .insert_resource(graph::GraphSettings {
+            metric: graph::DistanceMetric::Euclidean,
+            canvas_width: WIN_WIDTH,
+            canvas_height: WIN_HEIGHT,
+            directed: false,
+            asymmetry: 0.3,
+            open_path: false,
+            path_start: None,
+            path_end: None,
+            salesmen: 1,
+            depot: 1,
+            salesmen_objective: graph::SalesmenObjective::MinSum,
+            objective: graph::TourObjective::Length,
+        })
+        .insert_resource(graph::SolverGraph {
+            matrix: DMatrix::from_diagonal_element(0, 0, 0.0),
+            lengths: DMatrix::from_diagonal_element(0, 0, 0.0),
+            directed: false,
+            vertex_count: 0,
+            open_path: false,
+            dummy_weight: 0.0,
+            salesmen: 1,
+            depot: 1,
+            salesmen_objective: graph::SalesmenObjective::MinSum,
+            objective: graph::TourObjective::Length,
+        })
         .insert_resource(graph::AdjacencyMatrix {
             matrix: DMatrix::from_diagonal_element(0, 0, 0.0),
         })
         .insert_resource(graph::VertexList {
             vector: Vec::new(),
             count: 0,
+            points: Vec::new(),
+            demands: Vec::new(),
+            service_times: Vec::new(),
+            time_windows: Vec::new(),
+            prizes: Vec::new(),
+            clusters: Vec::new(),
         })
         .insert_resource(graph::EdgeList {
             vector: Vec::new(),
             count: 0,
+            overrides: Vec::new(),
+            fixed_weights: None,
+        })
+        .insert_resource(graph::EdgeSelection {
+            selected: None,
         })
         .insert_resource(graph::ShortestCycle {
             vector: Vec::new(),
             total_cycle_weight: 0.0,
+            routes: Vec::new(),
+            skipped: Vec::new(),
+        })
+        .insert_resource(graph::DisplaySettings {
+            pheromone_overlay: false,
         })
         .insert_resource(graph::AntColonyParameters{
             activate: false,
+            directed: false,
+            variant: graph::AntSystemVariant::Standard,
             number_of_ants: 50,
             pheromone_constant: 1.0,
             pheromone_evaporation_rate: 0.2,
             alpha: 1.0,
             beta: 4.0,
+            elitist_weight: 5.0,
+            rank_size: 6,
+            candidate_list_size: 20,
+            ant_start: graph::AntStartPolicy::Fixed,
+            local_search: local_search::LocalSearch::None,
+            seed: 0,
+            iteration: 0,
+            restart_branching_factor: 0.0,
+            restart_iterations: 0,
+            last_improvement: 0,
+            restarts: 0,
             pheromone_matrix: DMatrix::from_diagonal_element(0, 0, 0.0),
+            heuristic_matrix: DMatrix::from_diagonal_element(0, 0, 0.0),
+            candidate_lists: Vec::new(),
             ant_paths: Vec::new(),
+            best_path: None,
+        })
+        .insert_resource(graph::AntAnimation {
+            activate: false,
+            speed: 2.0,
+            follow_ant: 0,
+            progress: 0.0,
+            tours: Vec::new(),
+            rngs: Vec::new(),
+            choice_info: DMatrix::from_diagonal_element(0, 0, 0.0),
+        })
+        .insert_resource(graph::MultiColonyParameters {
+            activate: false,
+            number_of_colonies: 4,
+            colony_alphas: Vec::new(),
+            colony_betas: Vec::new(),
+            migration: graph::ColonyMigration::BestTour,
+            migration_interval: 10,
+            blend_rate: 0.1,
+            migrations: 0,
+            colonies: Vec::new(),
+        })
+        .insert_resource(exact::ExactSolverParameters {
+            activate: false,
+            started: None,
+            result: Default::default(),
+            cancel: Default::default(),
+        })
+        .insert_resource(vehicle_routing::VehicleRouting {
+            capacity: 10,
+            vehicles: 0,
+            route_local_search: true,
+            routes: Vec::new(),
+            depot: 1,
+            solver: String::new(),
+        })
+        .insert_resource(time_windows::TimeWindows {
+            speed: 1.0,
+            tour: Vec::new(),
+        })
+        .insert_resource(selective_routing::SelectiveRouting {
+            budget: 2000.0,
+            mode: selective_routing::SelectiveRoutingMode::Orienteering,
+            route: Vec::new(),
+            depot: 1,
+            solved: false,
+        })
+        .insert_resource(clusters::ClusterRouting {
+            mode: clusters::ClusterMode::Generalized,
+            solved: false,
+        })
+        .insert_resource(precedence::PrecedenceConstraints {
+            constraints: Vec::new(),
+            pairs: Vec::new(),
+            arrows: true,
+            depot: 1,
+            solved: false,
+        })
+        .insert_resource(dynamic::DynamicGraph {
+            activate: false,
+            changes: Vec::new(),
+            vertex_count: 0,
+        })
+        .insert_resource(view_3d::OrbitCamera {
+            yaw: 0.6,
+            pitch: 0.4,
+            distance: 2000.,
+            target: Vec3::ZERO,
+        })
+        .insert_resource(tsplib::OptimalTour {
+            tour: Vec::new(),
+            name: String::new(),
         })
         .insert_resource(ClearColor(Color::rgb(0.17254902, 0.176470588, 0.176470588)))
         .add_plugins(DefaultPlugins.set(WindowPlugin {
@@ -62,7 +211,22 @@ fn main() {
         .add_system(app::console_input)
         // -----------------------------
         .add_system(graph::graph_handler)
+        .add_system(dynamic::edit_vertices)
+        .add_system(view_3d::orbit_camera)
+        .add_system(view_3d::project_vertices)
+        .add_system(view_3d::draw_axes)
+        .add_system(graph::update_solver_graph)
         .add_system(graph::draw_graph)
+        .add_system(graph::draw_bottleneck_edge)
+        .add_system(tsplib::draw_optimal_tour)
         .add_system(graph::ant_colony_optimization)
+        .add_system(graph::draw_ants)
+        .add_system(graph::multi_colony_optimization)
+        .add_system(exact::exact_solver)
+        .add_system(vehicle_routing::draw_vehicle_routing)
+        .add_system(time_windows::draw_time_windows)
+        .add_system(selective_routing::draw_selective_routing)
+        .add_system(clusters::draw_clusters)
+        .add_system(precedence::draw_precedence)
         .run();
 }
\ No newline at end of file
@@ -1,5 +1,4 @@
 use crate::console;
-use crate::graph;
 
 use bevy::prelude::*;
 
@@ -24,6 +23,15 @@ pub struct EdgeCountText;
 #[derive(Component)]
 pub struct PossibleCyclesText;
 
+#[derive(Component)]
+pub struct AntColonyStatsText;
+
+#[derive(Component)]
+pub struct GraphWarningText;
+
+#[derive(Component)]
+pub struct OptimalTourText;
+
 // Spawns all of the entities that are going to be used to display information
 pub fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
     commands.spawn(Camera2dBundle::default());
@@ -234,16 +242,89 @@ pub fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
             }),
         PossibleCyclesText,
     ));
+
+    // Spawns a text bundle representing statistics of the running ant colony
+    commands.spawn((
+        // Create a TextBundle that has a Text with a single section.
+        TextBundle::from_section(
+            // Accepts a `String` or any type that converts into a `String`, such as `&str`
+            "",
+            TextStyle {
+                font: asset_server.load("fonts/FiraSans-ExtraLight.ttf"),
+                font_size: 30.0,
+                color: Color::WHITE,
+            },
+        ) // Set the alignment of the Text
+            .with_text_alignment(TextAlignment::Left)
+            // Set the style of the TextBundle itself.
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    top: Val::Px(120.0),
+                    left: Val::Px(15.0),
+                    ..default()
+                },
+                ..default()
+            }),
+        AntColonyStatsText,
+    ));
+
+    // Spawns a text bundle representing warnings about the graph, like a violated triangle inequality
+    commands.spawn((
+        // Create a TextBundle that has a Text with a single section.
+        TextBundle::from_section(
+            // Accepts a `String` or any type that converts into a `String`, such as `&str`
+            "",
+            TextStyle {
+                font: asset_server.load("fonts/FiraSans-ExtraLight.ttf"),
+                font_size: 25.0,
+                color: Color::ORANGE,
+            },
+        ) // Set the alignment of the Text
+            .with_text_alignment(TextAlignment::Left)
+            // Set the style of the TextBundle itself.
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    top: Val::Px(15.0),
+                    right: Val::Px(15.0),
+                    ..default()
+                },
+                ..default()
+            }),
+        GraphWarningText,
+    ));
+
+    // Spawns a text bundle representing the loaded optimal tour and the gap of the solver tour to it
+    commands.spawn((
+        // Create a TextBundle that has a Text with a single section.
+        TextBundle::from_section(
+            // Accepts a `String` or any type that converts into a `String`, such as `&str`
+            "",
+            TextStyle {
+                font: asset_server.load("fonts/FiraSans-ExtraLight.ttf"),
+                font_size: 30.0,
+                color: Color::FUCHSIA,
+            },
+        ) // Set the alignment of the Text
+            .with_text_alignment(TextAlignment::Left)
+            // Set the style of the TextBundle itself.
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    bottom: Val::Px(130.0),
+                    right: Val::Px(15.0),
+                    ..default()
+                },
+                ..default()
+            }),
+        OptimalTourText,
+    ));
 }
 
 // This system takes keyboard input and updates the console text on screen accordingly
 pub fn console_input(
-    app_exit_events: ResMut<Events<bevy::app::AppExit>>,
-    edit_mode: ResMut<graph::EditMode>,
-    vertex_list: ResMut<graph::VertexList>,
-    edge_list: ResMut<graph::EdgeList>,
-    shortest_cycle: ResMut<graph::ShortestCycle>,
-    ant_colony_parameters: ResMut<graph::AntColonyParameters>,
+    console_resources: console::ConsoleResources,
     mut char_evr: EventReader<ReceivedCharacter>,
     keys: Res<Input<KeyCode>>,
     mut console_past_command3: Local<String>,
@@ -269,6 +350,19 @@ pub fn console_input(
         }
     }
 
+    // A right clicked edge prefills the console with the command that changes its weight
+    if console_resources.edge_selection.is_changed()
+    {
+        if let Some((vertex1, vertex2)) = console_resources.edge_selection.selected
+        {
+            *string = format!("set edge {} {}: ", vertex1, vertex2);
+
+            for mut text in console_input_param_set.p0().iter_mut() {
+                text.sections[0].value = format!("{}", *string);
+            }
+        }
+    }
+
     if keys.just_pressed(KeyCode::Return) {
 
         *console_past_command3 = console_past_command2.to_string();
@@ -276,12 +370,7 @@ pub fn console_input(
         *console_past_command1 = string.to_string();
 
         console::execute_input(
-            app_exit_events,
-            edit_mode,
-            vertex_list,
-            edge_list,
-            shortest_cycle,
-            ant_colony_parameters,
+            console_resources,
             &string,
         );
 
@@ -0,0 +1,362 @@
+use crate::app;
+use crate::graph;
+use crate::local_search;
+use crate::vehicle_routing;
+
+use bevy::prelude::*;
+use bevy_prototype_debug_lines::*;
+use nalgebra::DMatrix;
+
+// Number of clusters from which the clustered tour is started, every start runs a full local search
+const CLUSTERED_TOUR_STARTS: usize = 10;
+
+// This resource stores which cluster constraints the last cluster solver followed, the clusters themselves are stored in the vertex list
+#[derive(Resource)]
+pub struct ClusterRouting {
+    pub mode: ClusterMode,
+    pub solved: bool,
+}
+
+// Routing problems on vertices grouped into clusters, a vertex without a cluster forms a cluster of its own
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ClusterMode {
+    // Generalized TSP, exactly one vertex of every cluster is visited
+    Generalized,
+    // Clustered TSP, every vertex is visited and the vertices of every cluster one right after another
+    Clustered,
+}
+
+// Groups the vertices by their cluster, clusters are ordered by their number and followed by the vertices without a cluster
+fn cluster_groups(
+    clusters: &Vec<u32>,
+) -> Vec<Vec<u32>> {
+    let mut cluster_numbers: Vec<u32> = clusters.iter().copied().filter(|cluster| *cluster > 0).collect();
+    cluster_numbers.sort();
+    cluster_numbers.dedup();
+
+    let mut groups: Vec<Vec<u32>> = cluster_numbers.iter()
+        .map(|cluster_number| (1..=clusters.len() as u32).filter(|vertex| clusters[*vertex as usize - 1] == *cluster_number).collect())
+        .collect();
+
+    groups.extend((1..=clusters.len() as u32).filter(|vertex| clusters[*vertex as usize - 1] == 0).map(|vertex| vec![vertex]));
+
+    return groups;
+}
+
+fn group_name(
+    group: &Vec<u32>,
+    clusters: &Vec<u32>,
+) -> String {
+    match clusters[group[0] as usize - 1] {
+        0 => format!("vertex {}", group[0]),
+        cluster => format!("cluster {}", cluster),
+    }
+}
+
+// Checks the cluster constraints on the tour stored in the shortest cycle, returns the description of every violated constraint
+pub fn validate_clusters(
+    shortest_cycle: &graph::ShortestCycle,
+    clusters: &Vec<u32>,
+    mode: ClusterMode,
+) -> Vec<String> {
+    let tour: Vec<u32> = shortest_cycle.vector.iter().map(|(vertex, _)| *vertex).collect();
+    let mut violations = Vec::new();
+
+    let visits = |vertex: &u32| tour.iter().filter(|tour_vertex| *tour_vertex == vertex).count();
+
+    for group in cluster_groups(clusters)
+    {
+        let group_visits: usize = group.iter().map(visits).sum();
+
+        match mode {
+            ClusterMode::Generalized => {
+                if group_visits != 1
+                {
+                    violations.push(format!("{} visited {} times instead of once", group_name(&group, clusters), group_visits));
+                }
+            }
+            ClusterMode::Clustered => {
+                for vertex in group.iter().filter(|vertex| visits(vertex) != 1)
+                {
+                    violations.push(format!("vertex {} visited {} times instead of once", vertex, visits(vertex)));
+                }
+
+                // Every time the tour steps into the cluster from outside starts another block of its vertices
+                let entries = (0..tour.len())
+                    .filter(|i| group.contains(&tour[*i]) && !group.contains(&tour[(*i + tour.len() - 1) % tour.len()]))
+                    .count();
+
+                if entries > 1
+                {
+                    violations.push(format!("{} is entered {} times instead of once", group_name(&group, clusters), entries));
+                }
+            }
+        }
+    }
+
+    return violations;
+}
+
+// Solves the chosen cluster problem on the current graph and stores the tour in the shortest cycle
+pub fn solve_clusters(
+    cluster_routing: &mut ClusterRouting,
+    mode: ClusterMode,
+    adjacency_matrix: &DMatrix<f32>,
+    vertex_list: &graph::VertexList,
+    graph_settings: &graph::GraphSettings,
+    shortest_cycle: &mut graph::ShortestCycle,
+) {
+    shortest_cycle.vector = Vec::new();
+    shortest_cycle.total_cycle_weight = 0.0;
+    shortest_cycle.routes = Vec::new();
+    shortest_cycle.skipped = Vec::new();
+    cluster_routing.solved = false;
+
+    if vertex_list.count < 2
+    {
+        println!("clusters: the graph needs at least two vertices");
+        return;
+    }
+
+    let groups = cluster_groups(&vertex_list.clusters);
+
+    let tour = match mode {
+        ClusterMode::Generalized => generalized_tour(adjacency_matrix, &groups, graph_settings.directed),
+        ClusterMode::Clustered => clustered_tour(adjacency_matrix, &groups, graph_settings.directed),
+    };
+
+    let length = local_search::tour_length(&tour, adjacency_matrix);
+
+    // A tour through a single cluster is stored as a loop on its vertex
+    if tour.len() > 0
+    {
+        for i in 0..tour.len()
+        {
+            shortest_cycle.vector.push((tour[i], tour[(i + 1) % tour.len()]));
+        }
+
+        shortest_cycle.routes.push((tour.len(), length));
+        shortest_cycle.total_cycle_weight = length;
+    }
+
+    if mode == ClusterMode::Generalized
+    {
+        shortest_cycle.skipped = (1..=vertex_list.count).filter(|vertex| !tour.contains(vertex)).collect();
+    }
+
+    println!("{:?}: tour {:?} with length {}", mode, tour, length);
+
+    // The validator result is printed once here, the draw system only keeps the text on screen up to date
+    for violation in validate_clusters(shortest_cycle, &vertex_list.clusters, mode)
+    {
+        println!("{:?}: {}", mode, violation);
+    }
+
+    cluster_routing.mode = mode;
+    cluster_routing.solved = true;
+}
+
+// Every vertex of the smallest cluster is tried as the start, the nearest vertex of every other cluster is picked and the tour through them
+// is improved by reordering it with 2-opt and Or-opt and by exchanging visited vertices for other vertices of the same cluster, the shortest tour is kept
+fn generalized_tour(
+    adjacency_matrix: &DMatrix<f32>,
+    groups: &Vec<Vec<u32>>,
+    directed: bool,
+) -> Vec<u32> {
+    let distance = |vertex1: u32, vertex2: u32| adjacency_matrix[(vertex1 as usize - 1, vertex2 as usize - 1)];
+
+    let mut group_of = vec![0; adjacency_matrix.nrows()];
+
+    for (index, group) in groups.iter().enumerate()
+    {
+        for vertex in group
+        {
+            group_of[*vertex as usize - 1] = index;
+        }
+    }
+
+    let smallest_group = groups.iter().min_by_key(|group| group.len()).unwrap();
+
+    let mut best_tour = Vec::new();
+    let mut best_length = f32::INFINITY;
+
+    for start in smallest_group
+    {
+        let mut tour: Vec<u32> = groups.iter()
+            .map(|group| *group.iter().min_by(|a, b| distance(*start, **a).total_cmp(&distance(*start, **b))).unwrap())
+            .collect();
+
+        loop
+        {
+            // The first vertex stays in place while the rest of the tour is reordered
+            let mut rest = tour[1..].to_vec();
+            vehicle_routing::improve_route(&mut rest, adjacency_matrix, tour[0], directed);
+            tour.truncate(1);
+            tour.extend(rest);
+
+            let mut changed = false;
+
+            for i in 0..tour.len()
+            {
+                let previous = tour[(i + tour.len() - 1) % tour.len()];
+                let next = tour[(i + 1) % tour.len()];
+                let detour = |vertex: u32| distance(previous, vertex) + distance(vertex, next);
+
+                let best_vertex = *groups[group_of[tour[i] as usize - 1]].iter().min_by(|a, b| detour(**a).total_cmp(&detour(**b))).unwrap();
+
+                if tour.len() > 1 && detour(best_vertex) < detour(tour[i]) - local_search::EPSILON
+                {
+                    tour[i] = best_vertex;
+                    changed = true;
+                }
+            }
+
+            if changed == false
+            {
+                break;
+            }
+        }
+
+        let length = local_search::tour_length(&tour, adjacency_matrix);
+
+        if best_tour.is_empty() || length < best_length
+        {
+            best_tour = tour;
+            best_length = length;
+        }
+    }
+
+    return best_tour;
+}
+
+// Every edge between two clusters is made longer by more than any local search move can gain, so starting from a tour that visits
+// the clusters one after another, 2-opt, Or-opt and segment exchanges only reorder the tour without ever splitting a cluster,
+// the nearest neighbour tour that finishes the current cluster first is started from the first vertex of several clusters and the shortest result is kept
+fn clustered_tour(
+    adjacency_matrix: &DMatrix<f32>,
+    groups: &Vec<Vec<u32>>,
+    directed: bool,
+) -> Vec<u32> {
+    let vertex_count = adjacency_matrix.nrows();
+
+    let mut group_of = vec![0; vertex_count];
+
+    for (index, group) in groups.iter().enumerate()
+    {
+        for vertex in group
+        {
+            group_of[*vertex as usize - 1] = index;
+        }
+    }
+
+    let longest_edge = adjacency_matrix.iter().copied().filter(|weight| weight.is_finite()).fold(0.0, f32::max);
+    let penalty = 4.0 * longest_edge + 1.0;
+
+    let penalized_matrix = DMatrix::from_fn(vertex_count, vertex_count, |i, j| {
+        if group_of[i] == group_of[j] { adjacency_matrix[(i, j)] } else { adjacency_matrix[(i, j)] + penalty }
+    });
+
+    let candidate_lists: Vec<Vec<u32>> = (0..vertex_count)
+        .map(|i| {
+            let mut neighbours: Vec<u32> = (1..=vertex_count as u32).filter(|j| *j as usize != i + 1).collect();
+            neighbours.sort_by(|a, b| penalized_matrix[(i, *a as usize - 1)].total_cmp(&penalized_matrix[(i, *b as usize - 1)]));
+            neighbours
+        })
+        .collect();
+
+    let mut best_tour = Vec::new();
+    let mut best_length = f32::INFINITY;
+
+    for start in groups.iter().take(CLUSTERED_TOUR_STARTS).map(|group| group[0])
+    {
+        let mut tour = vec![start];
+        let mut visited = vec![false; vertex_count];
+        visited[start as usize - 1] = true;
+
+        while tour.len() < vertex_count
+        {
+            let last = tour[tour.len() - 1] as usize - 1;
+
+            // The penalized weights prefer every unvisited vertex of the current cluster over any other vertex
+            let next = (1..=vertex_count as u32)
+                .filter(|vertex| !visited[*vertex as usize - 1])
+                .min_by(|a, b| penalized_matrix[(last, *a as usize - 1)].total_cmp(&penalized_matrix[(last, *b as usize - 1)]))
+                .unwrap();
+
+            visited[next as usize - 1] = true;
+            tour.push(next);
+        }
+
+        local_search::improve_tour(&mut tour, local_search::LocalSearch::ThreeOpt, &penalized_matrix, &candidate_lists, directed);
+        local_search::improve_tour(&mut tour, local_search::LocalSearch::OrOpt, &penalized_matrix, &candidate_lists, directed);
+
+        let length = local_search::tour_length(&tour, adjacency_matrix);
+
+        if best_tour.is_empty() || length < best_length
+        {
+            best_tour = tour;
+            best_length = length;
+        }
+    }
+
+    return best_tour;
+}
+
+// This system draws a ring in the colour of its cluster around every clustered vertex and reports whether the tour of the last cluster solver
+// satisfies the cluster constraints, the check is repeated whenever the clusters change
+pub fn draw_clusters(
+    mut lines: ResMut<DebugLines>,
+    vertex_list: Res<graph::VertexList>,
+    shortest_cycle: Res<graph::ShortestCycle>,
+    cluster_routing: Res<ClusterRouting>,
+    mut ant_colony_stats_text: Query<&mut Text, With<app::AntColonyStatsText>>,
+) {
+    for (vertex, y, x) in &vertex_list.vector
+    {
+        let cluster = vertex_list.clusters[*vertex as usize - 1];
+
+        if cluster == 0
+        {
+            continue;
+        }
+
+        let center = Vec3::new(*x, *y, 0.);
+
+        for radius in [18., 20.]
+        {
+            for k in 0..24
+            {
+                let angle1 = k as f32 / 24. * std::f32::consts::TAU;
+                let angle2 = (k + 1) as f32 / 24. * std::f32::consts::TAU;
+
+                lines.line_colored(
+                    center + Vec3::new(angle1.cos(), angle1.sin(), 0.) * radius,
+                    center + Vec3::new(angle2.cos(), angle2.sin(), 0.) * radius,
+                    0.0,
+                    graph::route_color(cluster as usize),
+                );
+            }
+        }
+    }
+
+    if cluster_routing.solved == true && (cluster_routing.is_changed() || vertex_list.is_changed())
+    {
+        let violations = validate_clusters(&shortest_cycle, &vertex_list.clusters, cluster_routing.mode);
+
+        let name = match cluster_routing.mode {
+            ClusterMode::Generalized => "Generalized TSP",
+            ClusterMode::Clustered => "Clustered TSP",
+        };
+
+        let stats = if violations.is_empty() {
+            format!("{}: tour length {:.1}, all cluster constraints satisfied", name, shortest_cycle.total_cycle_weight)
+        } else {
+            format!("{}: tour length {:.1}, cluster constraints violated\n{}", name, shortest_cycle.total_cycle_weight, violations.join("\n"))
+        };
+
+        for mut text in &mut ant_colony_stats_text
+        {
+            text.sections[0].value = stats.clone();
+        }
+    }
+}
@@ -1,17 +1,87 @@
+use crate::exact;
 use crate::graph;
+use crate::local_search;
+use crate::vehicle_routing;
+use crate::time_windows;
+use crate::selective_routing;
+use crate::clusters;
+use crate::dynamic;
+use crate::view_3d;
+use crate::precedence;
+use crate::tsplib;
 
 use nalgebra::DMatrix;
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
 
+// All of the resources that can be changed by console commands, grouped so the console system stays under the system parameter limit
+#[derive(SystemParam)]
+pub struct ConsoleResources<'w> {
+    pub app_exit_events: ResMut<'w, Events<bevy::app::AppExit>>,
+    pub edit_mode: ResMut<'w, graph::EditMode>,
+    pub graph_settings: ResMut<'w, graph::GraphSettings>,
+    pub adjacency_matrix: ResMut<'w, graph::AdjacencyMatrix>,
+    pub solver_graph: Res<'w, graph::SolverGraph>,
+    pub vertex_list: ResMut<'w, graph::VertexList>,
+    pub edge_list: ResMut<'w, graph::EdgeList>,
+    pub edge_selection: ResMut<'w, graph::EdgeSelection>,
+    pub shortest_cycle: ResMut<'w, graph::ShortestCycle>,
+    pub ant_colony_parameters: ResMut<'w, graph::AntColonyParameters>,
+    pub multi_colony_parameters: ResMut<'w, graph::MultiColonyParameters>,
+    pub exact_solver_parameters: ResMut<'w, exact::ExactSolverParameters>,
+    pub display_settings: ResMut<'w, graph::DisplaySettings>,
+    pub ant_animation: ResMut<'w, graph::AntAnimation>,
+    pub dynamic_graph: ResMut<'w, dynamic::DynamicGraph>,
+    pub variant_resources: VariantResources<'w>,
+}
+
+// Parameters and solutions of the routing problem variants, every variant keeps its own solution next to the shortest cycle,
+// together with the optimal tour of a loaded instance the solutions are compared to
+#[derive(SystemParam)]
+pub struct VariantResources<'w> {
+    pub vehicle_routing: ResMut<'w, vehicle_routing::VehicleRouting>,
+    pub time_windows: ResMut<'w, time_windows::TimeWindows>,
+    pub selective_routing: ResMut<'w, selective_routing::SelectiveRouting>,
+    pub cluster_routing: ResMut<'w, clusters::ClusterRouting>,
+    pub precedence_constraints: ResMut<'w, precedence::PrecedenceConstraints>,
+    pub optimal_tour: ResMut<'w, tsplib::OptimalTour>,
+}
+
+impl<'w> VariantResources<'w> {
+    // Forgets the solutions of all variants, so that they are not drawn on top of the tour of another solver
+    pub fn clear_solutions(&mut self) {
+        self.vehicle_routing.routes = Vec::new();
+        self.time_windows.tour = Vec::new();
+        self.selective_routing.route = Vec::new();
+        self.selective_routing.solved = false;
+        self.cluster_routing.solved = false;
+        self.precedence_constraints.solved = false;
+    }
+}
+
 pub fn execute_input(
-    mut app_exit_events: ResMut<Events<bevy::app::AppExit>>,
-    mut edit_mode: ResMut<graph::EditMode>,
-    mut vertex_list: ResMut<graph::VertexList>,
-    mut edge_list: ResMut<graph::EdgeList>,
-    mut shortest_cycle: ResMut<graph::ShortestCycle>,
-    mut ant_colony_parameters: ResMut<graph::AntColonyParameters>,
+    console_resources: ConsoleResources,
     console_input: &str,
 ){
+    let ConsoleResources {
+        mut app_exit_events,
+        mut edit_mode,
+        mut graph_settings,
+        mut adjacency_matrix,
+        solver_graph,
+        mut vertex_list,
+        mut edge_list,
+        mut edge_selection,
+        mut shortest_cycle,
+        mut ant_colony_parameters,
+        mut multi_colony_parameters,
+        mut exact_solver_parameters,
+        mut display_settings,
+        mut ant_animation,
+        mut dynamic_graph,
+        mut variant_resources,
+    } = console_resources;
+
     println!("execute command: {:?}", console_input);
 
     let parts = console_input.split(" ");
@@ -23,32 +93,629 @@ pub fn execute_input(
         println!("{}: {}", i,collection[i]);
     }
 
-    if collection[0] == "solve"
+    // The vehicles may return to the depot as often as needed, so the vehicle routing solvers do not need a hamiltonian cycle
+    if collection[0] == "solve" && collection[1] == "with" && (collection[2] == "clarke-wright\r" || collection[2] == "sweep\r")
     {
-        if collection[1] =="with"
+        println!("executing command: {:?}", console_input);
+
+        let solver = if collection[2] == "clarke-wright\r" {
+            vehicle_routing::VehicleRoutingSolver::ClarkeWright
+        } else {
+            vehicle_routing::VehicleRoutingSolver::Sweep
+        };
+
+        ant_colony_parameters.activate = false;
+        multi_colony_parameters.activate = false;
+        exact_solver_parameters.stop();
+        edit_mode.activate = true;
+
+        variant_resources.clear_solutions();
+
+        vehicle_routing::solve_vehicle_routing(
+            &mut variant_resources.vehicle_routing,
+            solver,
+            &adjacency_matrix.matrix,
+            &vertex_list,
+            &graph_settings,
+            &mut shortest_cycle,
+        );
+    } else if collection[0] == "solve" && collection[1] == "with" && collection[2] == "time-windows\r" {
+        println!("executing command: {:?}", console_input);
+
+        ant_colony_parameters.activate = false;
+        multi_colony_parameters.activate = false;
+        exact_solver_parameters.stop();
+        edit_mode.activate = true;
+
+        variant_resources.clear_solutions();
+
+        time_windows::solve_time_windows(
+            &mut variant_resources.time_windows,
+            &adjacency_matrix.matrix,
+            &vertex_list,
+            &graph_settings,
+            &mut shortest_cycle,
+        );
+    } else if collection[0] == "solve" && collection[1] == "with" && (collection[2] == "orienteering\r" || collection[2] == "prize-collecting\r") {
+        println!("executing command: {:?}", console_input);
+
+        let mode = if collection[2] == "orienteering\r" {
+            selective_routing::SelectiveRoutingMode::Orienteering
+        } else {
+            selective_routing::SelectiveRoutingMode::PrizeCollecting
+        };
+
+        ant_colony_parameters.activate = false;
+        multi_colony_parameters.activate = false;
+        exact_solver_parameters.stop();
+        edit_mode.activate = true;
+
+        variant_resources.clear_solutions();
+
+        selective_routing::solve_selective_routing(
+            &mut variant_resources.selective_routing,
+            mode,
+            &adjacency_matrix.matrix,
+            &vertex_list,
+            &graph_settings,
+            &mut shortest_cycle,
+        );
+    } else if collection[0] == "solve" && collection[1] == "with" && (collection[2] == "generalized\r" || collection[2] == "clustered\r") {
+        println!("executing command: {:?}", console_input);
+
+        let mode = if collection[2] == "generalized\r" {
+            clusters::ClusterMode::Generalized
+        } else {
+            clusters::ClusterMode::Clustered
+        };
+
+        ant_colony_parameters.activate = false;
+        multi_colony_parameters.activate = false;
+        exact_solver_parameters.stop();
+        edit_mode.activate = true;
+
+        variant_resources.clear_solutions();
+
+        clusters::solve_clusters(
+            &mut variant_resources.cluster_routing,
+            mode,
+            &adjacency_matrix.matrix,
+            &vertex_list,
+            &graph_settings,
+            &mut shortest_cycle,
+        );
+    } else if collection[0] == "solve" && collection[1] == "with" && collection[2] == "precedence\r" {
+        println!("executing command: {:?}", console_input);
+
+        ant_colony_parameters.activate = false;
+        multi_colony_parameters.activate = false;
+        exact_solver_parameters.stop();
+        edit_mode.activate = true;
+
+        variant_resources.clear_solutions();
+
+        let capacity = variant_resources.vehicle_routing.capacity;
+
+        precedence::solve_precedence(
+            &mut variant_resources.precedence_constraints,
+            &adjacency_matrix.matrix,
+            &vertex_list,
+            &graph_settings,
+            capacity,
+            &mut shortest_cycle,
+        );
+    } else if collection[0] == "solve"
+    {
+        // There is nothing to search for when the forbidden edges rule out every hamiltonian cycle
+        let obstruction = if graph::has_forbidden_edges(&edge_list) {
+            graph::find_hamiltonicity_obstruction(&solver_graph.matrix, solver_graph.directed)
+        } else {
+            None
+        };
+
+        if let Some(reason) = &obstruction
         {
-            if collection[2] == "ant-colony\r"
+            println!("no hamiltonian {} exists, {}", if solver_graph.open_path { "path" } else { "cycle" }, reason);
+        }
+
+        if collection[1] =="with" && obstruction.is_none()
+        {
+            let triangle_violation = if graph::may_violate_triangle_inequality(&edge_list, &graph_settings) {
+                graph::find_triangle_violation(&adjacency_matrix.matrix)
+            } else {
+                None
+            };
+
+            if let Some((vertex1, vertex2, vertex3)) = triangle_violation
+            {
+                println!("warning: solving a non-metric graph, the path {} - {} - {} is shorter than the edge {} - {}", vertex1, vertex2, vertex3, vertex1, vertex3);
+            }
+
+            let variant = match collection[2] {
+                "ant-colony\r" => Some(graph::AntSystemVariant::Standard),
+                "ant-colony-elitist\r" => Some(graph::AntSystemVariant::Elitist),
+                "ant-colony-rank\r" => Some(graph::AntSystemVariant::RankBased),
+                _ => None,
+            };
+
+            if let Some(variant) = variant
             {
                 println!("executing command: {:?}", console_input);
 
-                edit_mode.activate = false;
+                // The dynamic mode keeps the graph editable, the colony repairs itself after every edit
+                edit_mode.activate = dynamic_graph.activate;
+                dynamic_graph.changes = Vec::new();
+
+                graph::reset_colony(&mut ant_colony_parameters, solver_graph.matrix.nrows() as u32);
+                ant_animation.tours = Vec::new();
 
-                ant_colony_parameters.pheromone_matrix = DMatrix::from_diagonal_element(0, 0, 0.0);
-                ant_colony_parameters.ant_paths = Vec::new();
+                variant_resources.clear_solutions();
 
+                ant_colony_parameters.directed = solver_graph.directed;
+                ant_colony_parameters.variant = variant;
                 ant_colony_parameters.activate = true;
-                ant_colony_parameters.pheromone_matrix = DMatrix::from_diagonal_element(vertex_list.count as usize, vertex_list.count as usize, 0.0);
+                multi_colony_parameters.activate = false;
+                exact_solver_parameters.stop();
+            }
+
+            if collection[2] == "multi-colony\r"
+            {
+                println!("executing command: {:?}", console_input);
+
+                edit_mode.activate = dynamic_graph.activate;
+                dynamic_graph.changes = Vec::new();
+
+                multi_colony_parameters.colonies = Vec::new();
+                multi_colony_parameters.migrations = 0;
+                variant_resources.clear_solutions();
+
+                // Every colony starts as a copy of the single colony with its own alpha, beta and seed
+                for i in 0..multi_colony_parameters.number_of_colonies
+                {
+                    let mut colony = ant_colony_parameters.clone();
+
+                    graph::reset_colony(&mut colony, solver_graph.matrix.nrows() as u32);
+
+                    colony.activate = false;
+                    colony.directed = solver_graph.directed;
+                    colony.variant = graph::AntSystemVariant::Standard;
+                    colony.seed = ant_colony_parameters.seed.wrapping_add(i as u64);
+
+                    if multi_colony_parameters.colony_alphas.len() > 0
+                    {
+                        colony.alpha = multi_colony_parameters.colony_alphas[i as usize % multi_colony_parameters.colony_alphas.len()];
+                    }
+
+                    if multi_colony_parameters.colony_betas.len() > 0
+                    {
+                        colony.beta = multi_colony_parameters.colony_betas[i as usize % multi_colony_parameters.colony_betas.len()];
+                    }
+
+                    multi_colony_parameters.colonies.push(colony);
+                }
+
+                ant_colony_parameters.activate = false;
+                multi_colony_parameters.activate = multi_colony_parameters.colonies.len() > 0;
+                exact_solver_parameters.stop();
+            }
+
+            if collection[2] == "held-karp\r"
+            {
+                // An open path needs one extra dummy vertex and every additional salesman one copy of the depot
+                if solver_graph.matrix.nrows() as u32 > exact::HELD_KARP_VERTEX_LIMIT
+                {
+                    println!("held-karp: graphs with more than {} vertices are not supported", exact::HELD_KARP_VERTEX_LIMIT);
+                } else if solver_graph.salesmen > 1 && solver_graph.salesmen_objective == graph::SalesmenObjective::MinMax {
+                    println!("held-karp: only the min-sum objective of multiple salesmen is supported");
+                } else {
+                    println!("executing command: {:?}", console_input);
+
+                    edit_mode.activate = false;
+
+                    ant_colony_parameters.activate = false;
+                    multi_colony_parameters.activate = false;
+                    variant_resources.clear_solutions();
+
+                    exact::start_held_karp(&mut exact_solver_parameters, &solver_graph);
+                }
             }
         }
     }
 
     if collection[0] == "set"
     {
+        if collection[1] == "directed:"
+        {
+            if collection[2] !="\r"
+            {
+                if exact_solver_parameters.stop_running()
+                {
+                    edit_mode.activate = true;
+                }
+
+                graph_settings.directed = collection[2].replace("\r", "").parse().unwrap();
+                graph::rebuild_graph(&vertex_list, &mut edge_list, &mut adjacency_matrix, &graph_settings);
+                ant_colony_parameters.heuristic_matrix = DMatrix::from_diagonal_element(0, 0, 0.0);
+                ant_colony_parameters.candidate_lists = Vec::new();
+                println!("directed: {}", graph_settings.directed);
+            }
+        }
+
+        // set edge <vertex1> <vertex2>: <weight> overrides the weight of a single edge, reset brings back the computed one
+        if collection[1] == "edge" && collection.len() > 4
+        {
+            let vertex1: u32 = collection[2].parse().unwrap();
+            let vertex2: u32 = collection[3].replace(":", "").parse().unwrap();
+
+            if vertex1 == vertex2 || vertex1 < 1 || vertex2 < 1 || vertex1 > vertex_list.count || vertex2 > vertex_list.count
+            {
+                println!("edge {} - {} does not exist", vertex1, vertex2);
+            } else {
+                if exact_solver_parameters.stop_running()
+                {
+                    edit_mode.activate = true;
+                }
+
+                let directed = graph_settings.directed;
+                edge_list.overrides.retain(|(edge_vertex1, edge_vertex2, _)| !graph::same_edge(*edge_vertex1, *edge_vertex2, vertex1, vertex2, directed));
+
+                // Forbidden edges get an infinite weight, which every solver avoids
+                if collection[4] == "forbid\r"
+                {
+                    edge_list.overrides.push((vertex1, vertex2, f32::INFINITY));
+                } else if collection[4] != "reset\r" {
+                    let weight: f32 = collection[4].replace("\r", "").parse().unwrap();
+                    edge_list.overrides.push((vertex1, vertex2, weight));
+                }
+
+                graph::rebuild_graph(&vertex_list, &mut edge_list, &mut adjacency_matrix, &graph_settings);
+                ant_colony_parameters.heuristic_matrix = DMatrix::from_diagonal_element(0, 0, 0.0);
+                ant_colony_parameters.candidate_lists = Vec::new();
+                edge_selection.selected = None;
+                println!("edge {} - {}: {}", vertex1, vertex2, collection[4].replace("\r", ""));
+            }
+        }
+
+        if collection[1] == "metric:"
+        {
+            let metric = match collection[2] {
+                "euclidean\r" => Some(graph::DistanceMetric::Euclidean),
+                "rounded-euclidean\r" => Some(graph::DistanceMetric::RoundedEuclidean),
+                "manhattan\r" => Some(graph::DistanceMetric::Manhattan),
+                "chebyshev\r" => Some(graph::DistanceMetric::Chebyshev),
+                "squared-euclidean\r" => Some(graph::DistanceMetric::SquaredEuclidean),
+                "great-circle\r" => Some(graph::DistanceMetric::GreatCircle),
+                "toroidal\r" => Some(graph::DistanceMetric::Toroidal),
+                _ => None,
+            };
+
+            if let Some(metric) = metric
+            {
+                if exact_solver_parameters.stop_running()
+                {
+                    edit_mode.activate = true;
+                }
+
+                graph_settings.metric = metric;
+                graph::rebuild_graph(&vertex_list, &mut edge_list, &mut adjacency_matrix, &graph_settings);
+                ant_colony_parameters.heuristic_matrix = DMatrix::from_diagonal_element(0, 0, 0.0);
+                ant_colony_parameters.candidate_lists = Vec::new();
+                println!("metric: {:?}", graph_settings.metric);
+            }
+        }
+
+        if collection[1] == "open_path:"
+        {
+            if collection[2] !="\r"
+            {
+                graph_settings.open_path = collection[2].replace("\r", "").parse().unwrap();
+                println!("open_path: {}", graph_settings.open_path);
+            }
+        }
+
+        // Fixing the first or the last vertex switches to the open path mode, none releases the vertex again
+        if collection[1] == "start:" || collection[1] == "end:"
+        {
+            let vertex = match collection[2] {
+                "none\r" => None,
+                _ => Some(collection[2].replace("\r", "").parse::<u32>().unwrap()),
+            };
+
+            let other = if collection[1] == "start:" { graph_settings.path_end } else { graph_settings.path_start };
+
+            if vertex.is_some() && vertex == other
+            {
+                println!("the path can not start and end on the same vertex");
+            } else if vertex.map_or(false, |vertex| vertex < 1 || vertex > vertex_list.count) {
+                println!("vertex {} does not exist", vertex.unwrap());
+            } else {
+                if collection[1] == "start:"
+                {
+                    graph_settings.path_start = vertex;
+                } else {
+                    graph_settings.path_end = vertex;
+                }
+
+                if vertex.is_some()
+                {
+                    graph_settings.open_path = true;
+                }
+
+                println!("open_path: {}, start: {:?}, end: {:?}", graph_settings.open_path, graph_settings.path_start, graph_settings.path_end);
+            }
+        }
+
+        if collection[1] == "salesmen:"
+        {
+            if collection[2] !="\r"
+            {
+                graph_settings.salesmen = collection[2].replace("\r", "").parse::<u32>().unwrap().max(1);
+                println!("salesmen: {}", graph_settings.salesmen);
+            }
+        }
+
+        if collection[1] == "depot:"
+        {
+            if collection[2] !="\r"
+            {
+                graph_settings.depot = collection[2].replace("\r", "").parse().unwrap();
+                println!("depot: {}", graph_settings.depot);
+            }
+        }
+
+        // set demand <vertex>: <amount> changes the amount of goods the vertex orders
+        if collection[1] == "demand" && collection.len() > 3
+        {
+            let vertex: u32 = collection[2].replace(":", "").parse().unwrap();
+
+            if vertex < 1 || vertex > vertex_list.count
+            {
+                println!("vertex {} does not exist", vertex);
+            } else {
+                vertex_list.demands[vertex as usize - 1] = collection[3].replace("\r", "").parse().unwrap();
+                println!("demand {}: {}", vertex, vertex_list.demands[vertex as usize - 1]);
+            }
+        }
+
+        // set window <vertex>: <earliest> <latest> limits the arrival time at the vertex, none opens the window again
+        if collection[1] == "window" && collection.len() > 3
+        {
+            let vertex: u32 = collection[2].replace(":", "").parse().unwrap();
+
+            if vertex < 1 || vertex > vertex_list.count
+            {
+                println!("vertex {} does not exist", vertex);
+            } else {
+                vertex_list.time_windows[vertex as usize - 1] = if collection[3] == "none\r" || collection.len() < 5 {
+                    (0.0, f32::INFINITY)
+                } else {
+                    (collection[3].parse().unwrap(), collection[4].replace("\r", "").parse().unwrap())
+                };
+
+                println!("window {}: {:?}", vertex, vertex_list.time_windows[vertex as usize - 1]);
+            }
+        }
+
+        // set service <vertex>: <time> changes how long the salesman stays on the vertex
+        if collection[1] == "service" && collection.len() > 3
+        {
+            let vertex: u32 = collection[2].replace(":", "").parse().unwrap();
+
+            if vertex < 1 || vertex > vertex_list.count
+            {
+                println!("vertex {} does not exist", vertex);
+            } else {
+                vertex_list.service_times[vertex as usize - 1] = collection[3].replace("\r", "").parse().unwrap();
+                println!("service {}: {}", vertex, vertex_list.service_times[vertex as usize - 1]);
+            }
+        }
+
+        // set prize <vertex>: <prize> changes the prize collected on the vertex, which is also the penalty for skipping it
+        if collection[1] == "prize" && collection.len() > 3
+        {
+            let vertex: u32 = collection[2].replace(":", "").parse().unwrap();
+
+            if vertex < 1 || vertex > vertex_list.count
+            {
+                println!("vertex {} does not exist", vertex);
+            } else {
+                vertex_list.prizes[vertex as usize - 1] = collection[3].replace("\r", "").parse().unwrap();
+                println!("prize {}: {}", vertex, vertex_list.prizes[vertex as usize - 1]);
+            }
+        }
+
+        // set cluster <vertex1> <vertex2> ...: <cluster> assigns the vertices to a cluster, none removes them from their clusters
+        if collection[1] == "cluster" && collection.len() > 3
+        {
+            let cluster: u32 = match collection[collection.len() - 1] {
+                "none\r" => 0,
+                _ => collection[collection.len() - 1].replace("\r", "").parse().unwrap(),
+            };
+
+            for vertex in &collection[2..collection.len() - 1]
+            {
+                let vertex: u32 = vertex.replace(":", "").parse().unwrap();
+
+                if vertex < 1 || vertex > vertex_list.count
+                {
+                    println!("vertex {} does not exist", vertex);
+                } else {
+                    vertex_list.clusters[vertex as usize - 1] = cluster;
+                    println!("cluster {}: {}", vertex, cluster);
+                }
+            }
+        }
+
+        // set precedence <vertex1> <vertex2>: <bool> requires the first vertex to be visited before the second one,
+        // set pickup <pickup> <delivery>: <bool> adds a pair whose load is the demand of the pickup vertex
+        if (collection[1] == "precedence" || collection[1] == "pickup") && collection.len() > 4
+        {
+            let vertex1: u32 = collection[2].parse().unwrap();
+            let vertex2: u32 = collection[3].replace(":", "").parse().unwrap();
+            let value: bool = collection[4].replace("\r", "").parse().unwrap();
+
+            let constraints = if collection[1] == "precedence" {
+                &mut variant_resources.precedence_constraints.constraints
+            } else {
+                &mut variant_resources.precedence_constraints.pairs
+            };
+
+            if vertex1 < 1 || vertex1 > vertex_list.count || vertex2 < 1 || vertex2 > vertex_list.count || vertex1 == vertex2
+            {
+                println!("invalid vertices {} and {}", vertex1, vertex2);
+            } else {
+                constraints.retain(|constraint| *constraint != (vertex1, vertex2));
+
+                if value == true
+                {
+                    constraints.push((vertex1, vertex2));
+                }
+
+                println!("{} {} {}: {}", collection[1], vertex1, vertex2, value);
+            }
+        }
+
+        if collection[1] == "precedence_arrows:"
+        {
+            if collection[2] !="\r"
+            {
+                variant_resources.precedence_constraints.arrows = collection[2].replace("\r", "").parse().unwrap();
+                println!("precedence_arrows: {}", variant_resources.precedence_constraints.arrows);
+            }
+        }
+
+        if collection[1] == "budget:"
+        {
+            if collection[2] !="\r"
+            {
+                variant_resources.selective_routing.budget = collection[2].replace("\r", "").parse().unwrap();
+                println!("budget: {}", variant_resources.selective_routing.budget);
+            }
+        }
+
+        if collection[1] == "speed:"
+        {
+            if collection[2] !="\r"
+            {
+                let speed: f32 = collection[2].replace("\r", "").parse().unwrap();
+
+                // A vehicle that does not move would never arrive anywhere
+                if speed > 0.0
+                {
+                    variant_resources.time_windows.speed = speed;
+                    println!("speed: {}", variant_resources.time_windows.speed);
+                } else {
+                    println!("speed: has to be positive");
+                }
+            }
+        }
+
+        if collection[1] == "capacity:"
+        {
+            if collection[2] !="\r"
+            {
+                variant_resources.vehicle_routing.capacity = collection[2].replace("\r", "").parse().unwrap();
+                println!("capacity: {}", variant_resources.vehicle_routing.capacity);
+            }
+        }
+
+        if collection[1] == "vehicles:"
+        {
+            if collection[2] !="\r"
+            {
+                variant_resources.vehicle_routing.vehicles = collection[2].replace("\r", "").parse().unwrap();
+                println!("vehicles: {}", variant_resources.vehicle_routing.vehicles);
+            }
+        }
+
+        if collection[1] == "route_local_search:"
+        {
+            if collection[2] !="\r"
+            {
+                variant_resources.vehicle_routing.route_local_search = collection[2].replace("\r", "").parse().unwrap();
+                println!("route_local_search: {}", variant_resources.vehicle_routing.route_local_search);
+            }
+        }
+
+        if collection[1] == "salesmen_objective:"
+        {
+            let salesmen_objective = match collection[2] {
+                "min-sum\r" => Some(graph::SalesmenObjective::MinSum),
+                "min-max\r" => Some(graph::SalesmenObjective::MinMax),
+                _ => None,
+            };
+
+            if let Some(salesmen_objective) = salesmen_objective
+            {
+                graph_settings.salesmen_objective = salesmen_objective;
+                println!("salesmen_objective: {:?}", graph_settings.salesmen_objective);
+            }
+        }
+
+        if collection[1] == "dynamic:"
+        {
+            if collection[2] !="\r"
+            {
+                dynamic_graph.activate = collection[2].replace("\r", "").parse().unwrap();
+
+                // Switching the mode unfreezes or freezes a colony that is already running
+                if ant_colony_parameters.activate == true || multi_colony_parameters.activate == true
+                {
+                    dynamic_graph.changes = Vec::new();
+                    edit_mode.activate = dynamic_graph.activate;
+                }
+
+                println!("dynamic: {}", dynamic_graph.activate);
+            }
+        }
+
+        if collection[1] == "objective:"
+        {
+            let objective = match collection[2] {
+                "length\r" => Some(graph::TourObjective::Length),
+                "bottleneck\r" => Some(graph::TourObjective::Bottleneck),
+                "max-length\r" => Some(graph::TourObjective::MaxLength),
+                _ => None,
+            };
+
+            if let Some(objective) = objective
+            {
+                if exact_solver_parameters.stop_running()
+                {
+                    edit_mode.activate = true;
+                }
+
+                // The max-TSP reflects the weights the ants see, so the cached heuristic information is outdated
+                graph_settings.objective = objective;
+                ant_colony_parameters.heuristic_matrix = DMatrix::from_diagonal_element(0, 0, 0.0);
+                ant_colony_parameters.candidate_lists = Vec::new();
+                println!("objective: {:?}", graph_settings.objective);
+            }
+        }
+
+        if collection[1] == "asymmetry:"
+        {
+            if collection[2] !="\r"
+            {
+                if exact_solver_parameters.stop_running()
+                {
+                    edit_mode.activate = true;
+                }
+
+                graph_settings.asymmetry = collection[2].replace("\r", "").parse().unwrap();
+                graph::rebuild_graph(&vertex_list, &mut edge_list, &mut adjacency_matrix, &graph_settings);
+                ant_colony_parameters.heuristic_matrix = DMatrix::from_diagonal_element(0, 0, 0.0);
+                ant_colony_parameters.candidate_lists = Vec::new();
+                println!("asymmetry: {}", graph_settings.asymmetry);
+            }
+        }
+
         if collection[1] == "number_of_ants:"
         {
             if collection[2] !="\r"
             {
-                ant_colony_parameters.number_of_ants = collection[2].replace("\r", "").parse().unwrap();
+                // Without a single ant there would be no tour to deposit pheromones on
+                ant_colony_parameters.number_of_ants = collection[2].replace("\r", "").parse::<u32>().unwrap().max(1);
                 println!("number_of_ants: {}", ant_colony_parameters.number_of_ants);
             }
         }
@@ -85,21 +752,286 @@ pub fn execute_input(
             if collection[2] !="\r"
             {
                 ant_colony_parameters.beta = collection[2].replace("\r", "").parse().unwrap();
+                ant_colony_parameters.heuristic_matrix = DMatrix::from_diagonal_element(0, 0, 0.0);
                 println!("beta: {}", ant_colony_parameters.beta);
             }
         }
+
+        if collection[1] == "candidate_list_size:"
+        {
+            if collection[2] !="\r"
+            {
+                ant_colony_parameters.candidate_list_size = collection[2].replace("\r", "").parse().unwrap();
+                ant_colony_parameters.candidate_lists = Vec::new();
+                println!("candidate_list_size: {}", ant_colony_parameters.candidate_list_size);
+            }
+        }
+
+        if collection[1] == "ant_start:"
+        {
+            let ant_start = match collection[2] {
+                "fixed\r" => Some(graph::AntStartPolicy::Fixed),
+                "random\r" => Some(graph::AntStartPolicy::Random),
+                "round-robin\r" => Some(graph::AntStartPolicy::RoundRobin),
+                _ => None,
+            };
+
+            if let Some(ant_start) = ant_start
+            {
+                ant_colony_parameters.ant_start = ant_start;
+                println!("ant_start: {:?}", ant_colony_parameters.ant_start);
+            }
+        }
+
+        if collection[1] == "ant_local_search:"
+        {
+            let local_search = match collection[2] {
+                "none\r" => Some(local_search::LocalSearch::None),
+                "2-opt\r" => Some(local_search::LocalSearch::TwoOpt),
+                "or-opt\r" => Some(local_search::LocalSearch::OrOpt),
+                "3-opt\r" => Some(local_search::LocalSearch::ThreeOpt),
+                _ => None,
+            };
+
+            if let Some(local_search) = local_search
+            {
+                ant_colony_parameters.local_search = local_search;
+                println!("ant_local_search: {:?}", ant_colony_parameters.local_search);
+            }
+        }
+
+        if collection[1] == "restart_branching_factor:"
+        {
+            if collection[2] !="\r"
+            {
+                ant_colony_parameters.restart_branching_factor = collection[2].replace("\r", "").parse().unwrap();
+                println!("restart_branching_factor: {}", ant_colony_parameters.restart_branching_factor);
+            }
+        }
+
+        if collection[1] == "restart_iterations:"
+        {
+            if collection[2] !="\r"
+            {
+                ant_colony_parameters.restart_iterations = collection[2].replace("\r", "").parse().unwrap();
+                println!("restart_iterations: {}", ant_colony_parameters.restart_iterations);
+            }
+        }
+
+        if collection[1] == "pheromone_overlay:"
+        {
+            if collection[2] !="\r"
+            {
+                display_settings.pheromone_overlay = collection[2].replace("\r", "").parse().unwrap();
+                println!("pheromone_overlay: {}", display_settings.pheromone_overlay);
+            }
+        }
+
+        if collection[1] == "animate_ants:"
+        {
+            if collection[2] !="\r"
+            {
+                ant_animation.activate = collection[2].replace("\r", "").parse().unwrap();
+                println!("animate_ants: {}", ant_animation.activate);
+            }
+        }
+
+        if collection[1] == "ant_speed:"
+        {
+            if collection[2] !="\r"
+            {
+                ant_animation.speed = collection[2].replace("\r", "").parse().unwrap();
+                println!("ant_speed: {}", ant_animation.speed);
+            }
+        }
+
+        if collection[1] == "follow_ant:"
+        {
+            if collection[2] !="\r"
+            {
+                ant_animation.follow_ant = collection[2].replace("\r", "").parse().unwrap();
+                println!("follow_ant: {}", ant_animation.follow_ant);
+            }
+        }
+
+        if collection[1] == "number_of_colonies:"
+        {
+            if collection[2] !="\r"
+            {
+                // The multi-colony mode reads the first colony for the pheromone overlay and the animation
+                multi_colony_parameters.number_of_colonies = collection[2].replace("\r", "").parse::<u32>().unwrap().max(1);
+                println!("number_of_colonies: {}", multi_colony_parameters.number_of_colonies);
+            }
+        }
+
+        if collection[1] == "colony_alphas:"
+        {
+            multi_colony_parameters.colony_alphas = collection[2].replace("\r", "").split(",").filter(|value| !value.is_empty()).map(|value| value.parse().unwrap()).collect();
+            println!("colony_alphas: {:?}", multi_colony_parameters.colony_alphas);
+        }
+
+        if collection[1] == "colony_betas:"
+        {
+            multi_colony_parameters.colony_betas = collection[2].replace("\r", "").split(",").filter(|value| !value.is_empty()).map(|value| value.parse().unwrap()).collect();
+            println!("colony_betas: {:?}", multi_colony_parameters.colony_betas);
+        }
+
+        if collection[1] == "migration:"
+        {
+            let migration = match collection[2] {
+                "best-tour\r" => Some(graph::ColonyMigration::BestTour),
+                "pheromone-blend\r" => Some(graph::ColonyMigration::PheromoneBlend),
+                _ => None,
+            };
+
+            if let Some(migration) = migration
+            {
+                multi_colony_parameters.migration = migration;
+                println!("migration: {:?}", multi_colony_parameters.migration);
+            }
+        }
+
+        if collection[1] == "migration_interval:"
+        {
+            if collection[2] !="\r"
+            {
+                multi_colony_parameters.migration_interval = collection[2].replace("\r", "").parse().unwrap();
+                println!("migration_interval: {}", multi_colony_parameters.migration_interval);
+            }
+        }
+
+        if collection[1] == "blend_rate:"
+        {
+            if collection[2] !="\r"
+            {
+                multi_colony_parameters.blend_rate = collection[2].replace("\r", "").parse().unwrap();
+                println!("blend_rate: {}", multi_colony_parameters.blend_rate);
+            }
+        }
+
+        if collection[1] == "seed:"
+        {
+            if collection[2] !="\r"
+            {
+                ant_colony_parameters.seed = collection[2].replace("\r", "").parse().unwrap();
+                println!("seed: {}", ant_colony_parameters.seed);
+            }
+        }
+
+        if collection[1] == "elitist_weight:"
+        {
+            if collection[2] !="\r"
+            {
+                ant_colony_parameters.elitist_weight = collection[2].replace("\r", "").parse().unwrap();
+                println!("elitist_weight: {}", ant_colony_parameters.elitist_weight);
+            }
+        }
+
+        if collection[1] == "rank_size:"
+        {
+            if collection[2] !="\r"
+            {
+                ant_colony_parameters.rank_size = collection[2].replace("\r", "").parse().unwrap();
+                println!("rank_size: {}", ant_colony_parameters.rank_size);
+            }
+        }
     }
 
-    if console_input == "clear\r"
+    // generate 3d: <count> replaces the graph by a 3D instance of random points, so the graph is cleared first
+    let generate_3d = collection[0] == "generate" && collection.len() > 2 && collection[1] == "3d:";
+
+    // load <path> replaces the graph by a TSPLIB instance, a file that can not be parsed leaves the graph as it is
+    let loaded_instance = if collection[0] == "load" && collection.len() > 1 && collection[1].trim() != "tour" {
+        let path = collection[1..].join(" ").replace("\r", "");
+
+        match tsplib::read_instance(&path) {
+            Ok(instance) => Some(instance),
+            Err(error) => {
+                println!("load {}: {}", path, error);
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    if console_input == "clear\r" || generate_3d == true || loaded_instance.is_some()
     {
-        vertex_list.vector = Vec::new();
-        vertex_list.count = 0;
+        // The running solvers would keep the pheromones and tours of the replaced graph
+        ant_colony_parameters.activate = false;
+        multi_colony_parameters.activate = false;
+        exact_solver_parameters.stop();
+        edit_mode.activate = true;
+
+        vertex_list.clear_vertices();
 
-        edge_list.vector = Vec::new();
-        edge_list.count = 0;
+        edge_list.overrides = Vec::new();
+        edge_list.fixed_weights = None;
+        edge_selection.selected = None;
+        graph::rebuild_graph(&vertex_list, &mut edge_list, &mut adjacency_matrix, &graph_settings);
 
         shortest_cycle.vector = Vec::new();
         shortest_cycle.total_cycle_weight = 0.0;
+        shortest_cycle.routes = Vec::new();
+        shortest_cycle.skipped = Vec::new();
+        variant_resources.clear_solutions();
+        variant_resources.precedence_constraints.constraints = Vec::new();
+        variant_resources.precedence_constraints.pairs = Vec::new();
+        variant_resources.optimal_tour.tour = Vec::new();
+    }
+
+    if generate_3d == true
+    {
+        let count: u32 = collection[2].replace("\r", "").parse().unwrap();
+
+        view_3d::generate_instance(&mut vertex_list, count, ant_colony_parameters.seed);
+        graph::rebuild_graph(&vertex_list, &mut edge_list, &mut adjacency_matrix, &graph_settings);
+
+        println!("generated 3d instance with {} vertices", count);
+    }
+
+    if let Some(instance) = loaded_instance
+    {
+        tsplib::load_instance(instance, &mut vertex_list, &mut edge_list, &mut adjacency_matrix, &mut graph_settings);
+    }
+
+    // load tour <path> reads the optimal tour of the current instance, save tour <path> writes the drawn tour in the same format
+    let tour_path = if (collection[0] == "load" || collection[0] == "save") && collection.len() > 1 && collection[1].trim() == "tour" {
+        let path = collection[2..].join(" ").trim().to_string();
+
+        if path.is_empty()
+        {
+            println!("usage: {} tour <path>", collection[0]);
+            None
+        } else {
+            Some(path)
+        }
+    } else {
+        None
+    };
+
+    if let Some(path) = tour_path
+    {
+        if collection[0] == "load"
+        {
+            match tsplib::read_tour(&path) {
+                Ok((_, tour)) if tour.len() != vertex_list.count as usize => {
+                    println!("load tour {}: the tour visits {} vertices, but the graph has {}", path, tour.len(), vertex_list.count);
+                }
+                Ok((name, tour)) => {
+                    println!("loaded tour {:?}", tour);
+
+                    variant_resources.optimal_tour.name = if name.is_empty() { path } else { name };
+                    variant_resources.optimal_tour.tour = tour;
+                }
+                Err(error) => println!("load tour {}: {}", path, error),
+            }
+        } else {
+            match tsplib::cycle_tour(&shortest_cycle, vertex_list.count).and_then(|tour| tsplib::write_tour(&path, &tour, shortest_cycle.total_cycle_weight)) {
+                Ok(()) => println!("saved tour to {}", path),
+                Err(error) => println!("save tour {}: {}", path, error),
+            }
+        }
     }
 
     if console_input == "stop\r"
@@ -107,6 +1039,8 @@ pub fn execute_input(
         println!("executing command: {:?}", console_input);
 
         ant_colony_parameters.activate = false;
+        multi_colony_parameters.activate = false;
+        exact_solver_parameters.stop();
         edit_mode.activate = true;
     }
 
@@ -0,0 +1,281 @@
+use crate::app;
+use crate::console;
+use crate::graph;
+use crate::local_search;
+
+use bevy::prelude::*;
+use nalgebra::DMatrix;
+
+// This resource stores the edits made to the graph while the ant colonies keep running, the colonies repair their pheromones
+// and best tours with them instead of starting over
+#[derive(Resource)]
+pub struct DynamicGraph {
+    // Keeps the graph editable while the ant colonies run
+    pub activate: bool,
+    // Edits the colonies have not repaired yet, in the order they were made
+    pub changes: Vec<GraphChange>,
+    // Number of vertices after the last edit, the colonies wait until the solver graph has caught up with it
+    pub vertex_count: u32,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GraphChange {
+    // Vertex appended as the last one
+    Added(u32),
+    // Vertex deleted, the following vertices are numbered one lower
+    Removed(u32),
+    // Vertex dragged to another position, all of its edges got new weights
+    Moved(u32),
+}
+
+// Returns the vertex drawn under the given canvas position
+fn vertex_at(
+    vertex_list: &graph::VertexList,
+    position: Vec2,
+) -> Option<u32> {
+    vertex_list.vector.iter()
+        .filter(|(_, y, x)| Vec2::new(*x, *y).distance(position) <= 15.)
+        .min_by(|(_, y1, x1), (_, y2, x2)| Vec2::new(*x1, *y1).distance(position).total_cmp(&Vec2::new(*x2, *y2).distance(position)))
+        .map(|(vertex, _, _)| *vertex)
+}
+
+// This system lets the user delete a vertex with a middle click and drag a vertex to another position while holding shift,
+// both edits are recorded for the ant colonies of the dynamic mode
+pub fn edit_vertices(
+    mouse_button_input: Res<Input<MouseButton>>,
+    keyboard_input: Res<Input<KeyCode>>,
+    window: Query<&mut Window>,
+    edit_mode: Res<graph::EditMode>,
+    mut graph_settings: ResMut<graph::GraphSettings>,
+    mut adjacency_matrix: ResMut<graph::AdjacencyMatrix>,
+    mut vertex_list: ResMut<graph::VertexList>,
+    mut edge_list: ResMut<graph::EdgeList>,
+    mut edge_selection: ResMut<graph::EdgeSelection>,
+    mut shortest_cycle: ResMut<graph::ShortestCycle>,
+    mut ant_animation: ResMut<graph::AntAnimation>,
+    mut dynamic_graph: ResMut<DynamicGraph>,
+    mut variant_resources: console::VariantResources,
+    mut dragged_vertex: Local<Option<u32>>,
+) {
+    if edit_mode.activate == false
+    {
+        *dragged_vertex = None;
+        return;
+    }
+
+    let win = window.single();
+    let cursor = Vec2::new(
+        app::get_cursor_position(win).x - (win.width() / 2.),
+        app::get_cursor_position(win).y - (win.height() / 2.),
+    );
+    let shift = keyboard_input.pressed(KeyCode::LShift) || keyboard_input.pressed(KeyCode::RShift);
+
+    // The points of 3D instances are fixed in space, the canvas only shows their projection
+    if mouse_button_input.just_pressed(MouseButton::Left) && shift == true && vertex_list.points.is_empty()
+    {
+        *dragged_vertex = vertex_at(&vertex_list, cursor);
+    }
+
+    if let Some(vertex) = *dragged_vertex
+    {
+        let (_, y, x) = vertex_list.vector[vertex as usize - 1];
+
+        if Vec2::new(x, y) != cursor
+        {
+            vertex_list.vector[vertex as usize - 1] = (vertex, cursor.y, cursor.x);
+            graph::rebuild_graph(&vertex_list, &mut edge_list, &mut adjacency_matrix, &graph_settings);
+        }
+
+        // The colonies only repair the vertex once it is dropped, the weights are updated on every frame of the drag
+        if mouse_button_input.just_released(MouseButton::Left)
+        {
+            println!("moved vertex: {}", vertex);
+
+            dynamic_graph.changes.push(GraphChange::Moved(vertex));
+            *dragged_vertex = None;
+        }
+    }
+
+    if mouse_button_input.just_pressed(MouseButton::Middle)
+    {
+        if let Some(vertex) = vertex_at(&vertex_list, cursor)
+        {
+            // The colonies need at least a triangle to build tours on
+            if dynamic_graph.activate == true && vertex_list.count <= 3
+            {
+                println!("the dynamic mode keeps at least 3 vertices");
+                return;
+            }
+
+            let renumber = |other: u32| if other > vertex { other - 1 } else { other };
+
+            vertex_list.remove_vertex(vertex);
+
+            edge_list.overrides.retain(|(vertex1, vertex2, _)| *vertex1 != vertex && *vertex2 != vertex);
+
+            if let Some(weights) = edge_list.fixed_weights.take()
+            {
+                edge_list.fixed_weights = Some(if (vertex as usize) <= weights.nrows() {
+                    weights.remove_row(vertex as usize - 1).remove_column(vertex as usize - 1)
+                } else {
+                    weights
+                });
+            }
+
+            for (vertex1, vertex2, _) in edge_list.overrides.iter_mut()
+            {
+                (*vertex1, *vertex2) = (renumber(*vertex1), renumber(*vertex2));
+            }
+
+            edge_selection.selected = None;
+            graph::rebuild_graph(&vertex_list, &mut edge_list, &mut adjacency_matrix, &graph_settings);
+
+            // A removed depot or end of the path falls back to the defaults
+            graph_settings.depot = if graph_settings.depot == vertex { 1 } else { renumber(graph_settings.depot) };
+            graph_settings.path_start = graph_settings.path_start.filter(|start| *start != vertex).map(renumber);
+            graph_settings.path_end = graph_settings.path_end.filter(|end| *end != vertex).map(renumber);
+
+            let precedence_constraints = &mut *variant_resources.precedence_constraints;
+
+            for constraints in [&mut precedence_constraints.constraints, &mut precedence_constraints.pairs]
+            {
+                constraints.retain(|(before, after)| *before != vertex && *after != vertex);
+
+                for (before, after) in constraints.iter_mut()
+                {
+                    (*before, *after) = (renumber(*before), renumber(*after));
+                }
+            }
+
+            // The optimal tour belongs to the instance as it was loaded
+            variant_resources.optimal_tour.tour = Vec::new();
+
+            // Every drawn tour refers to the old numbers, the ant colonies store their repaired best tour again after the next iteration
+            shortest_cycle.vector = Vec::new();
+            shortest_cycle.total_cycle_weight = 0.0;
+            shortest_cycle.routes = Vec::new();
+            shortest_cycle.skipped = Vec::new();
+            ant_animation.tours = Vec::new();
+            variant_resources.clear_solutions();
+            *dragged_vertex = None;
+
+            println!("removed vertex: {}", vertex);
+
+            dynamic_graph.changes.push(GraphChange::Removed(vertex));
+        }
+    }
+
+    dynamic_graph.vertex_count = vertex_list.count;
+}
+
+// The colonies wait with the repair until the solver graph has been rebuilt for the last edit
+pub fn solver_graph_current(
+    dynamic_graph: &DynamicGraph,
+    solver_graph: &graph::SolverGraph,
+) -> bool {
+    dynamic_graph.changes.is_empty() || solver_graph.vertex_count == dynamic_graph.vertex_count
+}
+
+// Applies the edits to the pheromone matrix and the best tour of a colony and rates the best tour again on the current solver graph,
+// new and moved vertices get the average pheromone on all of their edges and are inserted into the best tour where it grows the least,
+// removed vertices are cut out of both, the extra vertices of open paths and multiple salesmen follow the real ones and move with them
+pub fn repair_colony(
+    ant_colony_parameters: &mut graph::AntColonyParameters,
+    changes: &Vec<GraphChange>,
+    solver_graph: &graph::SolverGraph,
+) {
+    let mut pheromone_matrix = ant_colony_parameters.pheromone_matrix.clone();
+    let mut best_tour = ant_colony_parameters.best_path.clone().map(|best_path| best_path.0);
+    let mut pending_vertices: Vec<u32> = Vec::new();
+
+    for change in changes
+    {
+        match *change {
+            GraphChange::Added(vertex) => {
+                let shift = |other: &mut u32| if *other >= vertex { *other += 1 };
+                let average = average_pheromone(&pheromone_matrix);
+
+                pheromone_matrix = pheromone_matrix.insert_row(vertex as usize - 1, average).insert_column(vertex as usize - 1, average);
+                pheromone_matrix[(vertex as usize - 1, vertex as usize - 1)] = 0.0;
+
+                best_tour.iter_mut().flatten().for_each(shift);
+                pending_vertices.iter_mut().for_each(shift);
+                pending_vertices.push(vertex);
+            }
+            GraphChange::Removed(vertex) => {
+                let shift = |other: &mut u32| if *other > vertex { *other -= 1 };
+
+                if (vertex as usize) <= pheromone_matrix.nrows()
+                {
+                    pheromone_matrix = pheromone_matrix.remove_row(vertex as usize - 1).remove_column(vertex as usize - 1);
+                }
+
+                for tour in [best_tour.as_mut(), Some(&mut pending_vertices)].into_iter().flatten()
+                {
+                    tour.retain(|other| *other != vertex);
+                    tour.iter_mut().for_each(shift);
+                }
+            }
+            GraphChange::Moved(vertex) => {
+                let average = average_pheromone(&pheromone_matrix);
+
+                if (vertex as usize) <= pheromone_matrix.nrows()
+                {
+                    pheromone_matrix.row_mut(vertex as usize - 1).fill(average);
+                    pheromone_matrix.column_mut(vertex as usize - 1).fill(average);
+                    pheromone_matrix[(vertex as usize - 1, vertex as usize - 1)] = 0.0;
+                }
+
+                best_tour.iter_mut().for_each(|tour| tour.retain(|other| *other != vertex));
+                pending_vertices.retain(|other| *other != vertex);
+                pending_vertices.push(vertex);
+            }
+        }
+    }
+
+    // Edits the repair does not know about, like another number of salesmen, leave no choice but to start over
+    if pheromone_matrix.nrows() != solver_graph.matrix.nrows()
+    {
+        graph::reset_colony(ant_colony_parameters, solver_graph.matrix.nrows() as u32);
+        return;
+    }
+
+    ant_colony_parameters.pheromone_matrix = pheromone_matrix;
+
+    // The cached heuristic information and the tours of the running iteration belong to the old graph
+    ant_colony_parameters.heuristic_matrix = DMatrix::from_diagonal_element(0, 0, 0.0);
+    ant_colony_parameters.candidate_lists = Vec::new();
+    ant_colony_parameters.ant_paths = Vec::new();
+
+    ant_colony_parameters.best_path = best_tour.map(|mut tour| {
+        for vertex in pending_vertices
+        {
+            let (position, _) = local_search::cheapest_insertion(&tour, vertex, &solver_graph.matrix);
+            tour.insert(position, vertex);
+        }
+
+        if let Some(start_index) = tour.iter().position(|vertex| *vertex == 1)
+        {
+            tour.rotate_left(start_index);
+        }
+
+        let tour_length = local_search::tour_length(&tour, &solver_graph.matrix);
+        let cost = graph::tour_cost(&tour, tour_length, solver_graph);
+
+        (tour, cost)
+    }).filter(|best_path| best_path.0.len() > 0);
+}
+
+// Average pheromone on the edges of the matrix, the diagonal is left out
+fn average_pheromone(
+    pheromone_matrix: &DMatrix<f32>,
+) -> f32 {
+    let vertex_count = pheromone_matrix.nrows();
+
+    if vertex_count < 2
+    {
+        return 0.0;
+    }
+
+    (pheromone_matrix.sum() - pheromone_matrix.trace()) / (vertex_count * (vertex_count - 1)) as f32
+}
@@ -0,0 +1,292 @@
+use crate::app;
+use crate::graph;
+
+use bevy::prelude::*;
+use nalgebra::DMatrix;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+// Held-Karp stores 2^(n - 1) * (n - 1) partial paths, beyond this limit the table would not fit into memory
+pub const HELD_KARP_VERTEX_LIMIT: u32 = 20;
+
+// This resource stores the state of the exact solvers, they run on a separate thread so that the window stays responsive
+#[derive(Resource)]
+pub struct ExactSolverParameters {
+    pub activate: bool,
+    pub started: Option<Instant>,
+    // Filled in by the solver thread once it finishes, the inner None means that no hamiltonian cycle exists, the result of a cancelled run is never read
+    pub result: Arc<Mutex<Option<Option<(Vec<u32>, f32)>>>>,
+    // Set to stop the solver thread of the current run, every run gets its own flag
+    pub cancel: Arc<AtomicBool>,
+}
+
+impl ExactSolverParameters {
+    // Stops waiting for the solver thread and lets the thread give up its search as well, so it does not keep a core busy
+    pub fn stop(&mut self) {
+        self.activate = false;
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+
+    // Stops a running search because the graph it searches is about to change, returns whether a search was running
+    pub fn stop_running(&mut self) -> bool {
+        let running = self.activate;
+        self.stop();
+
+        return running;
+    }
+}
+
+// Starts the Held-Karp algorithm on a copy of the solver graph, the max-TSP is already turned into a shortest tour by the reflected weights
+pub fn start_held_karp(
+    exact_solver_parameters: &mut ExactSolverParameters,
+    solver_graph: &graph::SolverGraph,
+) {
+    let adjacency_matrix = solver_graph.matrix.clone();
+    let objective = solver_graph.objective;
+
+    // The dummy vertex of an open path is not part of the path, so its edges never become the bottleneck
+    let real_vertices = if solver_graph.open_path { solver_graph.vertex_count as usize } else { adjacency_matrix.nrows() };
+
+    // Every run gets its own result slot, so a stopped run that finishes later can not overwrite a newer one
+    let result = Arc::new(Mutex::new(None));
+    let thread_result = result.clone();
+
+    exact_solver_parameters.stop();
+    let cancel = Arc::new(AtomicBool::new(false));
+    let thread_cancel = cancel.clone();
+
+    std::thread::spawn(move || {
+        let tour = match objective {
+            graph::TourObjective::Bottleneck => bottleneck_held_karp(&adjacency_matrix, real_vertices, &thread_cancel),
+            _ => held_karp(&adjacency_matrix, &thread_cancel),
+        };
+        *thread_result.lock().unwrap() = Some(tour);
+    });
+
+    exact_solver_parameters.result = result;
+    exact_solver_parameters.cancel = cancel;
+    exact_solver_parameters.started = Some(Instant::now());
+    exact_solver_parameters.activate = true;
+}
+
+// This system waits for the exact solver thread and draws the optimal tour once it is done
+pub fn exact_solver(
+    mut exact_solver_parameters: ResMut<ExactSolverParameters>,
+    mut edit_mode: ResMut<graph::EditMode>,
+    mut shortest_cycle: ResMut<graph::ShortestCycle>,
+    solver_graph: Res<graph::SolverGraph>,
+    mut ant_colony_stats_text: Query<&mut Text, With<app::AntColonyStatsText>>,
+) {
+    // The solver thread searches a copy of the graph it was started on, so its tour would not belong to the changed graph
+    if exact_solver_parameters.activate == true && solver_graph.is_changed()
+    {
+        exact_solver_parameters.stop();
+        edit_mode.activate = true;
+
+        println!("held-karp: the graph changed, the search was cancelled");
+
+        for mut text in &mut ant_colony_stats_text
+        {
+            text.sections[0].value = "Held-Karp: the graph changed, the search was cancelled".to_string();
+        }
+    }
+
+    if exact_solver_parameters.activate == true
+    {
+        let result = exact_solver_parameters.result.lock().unwrap().take();
+        let elapsed = exact_solver_parameters.started.map(|started| started.elapsed().as_secs_f32()).unwrap_or(0.0);
+
+        let stats = match result {
+            Some(Some((tour, length))) => {
+                exact_solver_parameters.activate = false;
+                edit_mode.activate = true;
+
+                graph::store_shortest_cycle(&tour, length, &solver_graph, &mut shortest_cycle);
+
+                println!("held-karp: optimal tour {:?} with length {}", tour, shortest_cycle.total_cycle_weight);
+
+                format!("Held-Karp: optimal tour length {:.1} found in {:.2} s", shortest_cycle.total_cycle_weight, elapsed)
+                    + &graph::route_summary(&shortest_cycle)
+                    + &graph::objective_summary(&shortest_cycle, &solver_graph)
+            }
+            Some(None) => {
+                exact_solver_parameters.activate = false;
+                edit_mode.activate = true;
+
+                shortest_cycle.vector = Vec::new();
+                shortest_cycle.total_cycle_weight = 0.0;
+                shortest_cycle.routes = Vec::new();
+                shortest_cycle.skipped = Vec::new();
+
+                println!("held-karp: no hamiltonian cycle avoids the forbidden edges");
+
+                format!("Held-Karp: no hamiltonian cycle avoids the forbidden edges, searched for {:.2} s", elapsed)
+            }
+            None => format!("Held-Karp: searching for the optimal tour... {:.0} s", elapsed),
+        };
+
+        for mut text in &mut ant_colony_stats_text
+        {
+            text.sections[0].value = stats.clone();
+        }
+    }
+}
+
+// Dynamic programming over subsets of vertices, finds the shortest hamiltonian cycle starting on vertex 1,
+// the adjacency matrix is read from row to column, so directed graphs are supported as well,
+// returns None when every cycle uses a forbidden edge or the run was cancelled
+pub fn held_karp(
+    adjacency_matrix: &DMatrix<f32>,
+    cancel: &AtomicBool,
+) -> Option<(Vec<u32>, f32)> {
+    let vertex_count = adjacency_matrix.nrows();
+
+    if vertex_count < 3
+    {
+        let tour: Vec<u32> = (1..=vertex_count as u32).collect();
+        let length = if vertex_count == 2 { adjacency_matrix[(0, 1)] + adjacency_matrix[(1, 0)] } else { 0.0 };
+
+        return Some((tour, length)).filter(|(_, length)| length.is_finite());
+    }
+
+    // Vertex 1 is always the start, bit j of a subset stands for vertex j + 2
+    let m = vertex_count - 1;
+    let subsets = 1usize << m;
+
+    let mut length = vec![f32::INFINITY; subsets * m];
+    let mut parent = vec![u8::MAX; subsets * m];
+
+    for j in 0..m
+    {
+        length[(1 << j) * m + j] = adjacency_matrix[(0, j + 1)];
+    }
+
+    for subset in 1..subsets
+    {
+        if cancel.load(Ordering::Relaxed)
+        {
+            return None;
+        }
+
+        for j in 0..m
+        {
+            if subset & (1 << j) == 0
+            {
+                continue;
+            }
+
+            let current_length = length[subset * m + j];
+
+            if current_length == f32::INFINITY
+            {
+                continue;
+            }
+
+            for k in 0..m
+            {
+                if subset & (1 << k) != 0
+                {
+                    continue;
+                }
+
+                let next_subset = subset | (1 << k);
+                let next_length = current_length + adjacency_matrix[(j + 1, k + 1)];
+
+                if next_length < length[next_subset * m + k]
+                {
+                    length[next_subset * m + k] = next_length;
+                    parent[next_subset * m + k] = j as u8;
+                }
+            }
+        }
+    }
+
+    let full_subset = subsets - 1;
+    let mut best_length = f32::INFINITY;
+    let mut last = 0;
+
+    for j in 0..m
+    {
+        let cycle_length = length[full_subset * m + j] + adjacency_matrix[(j + 1, 0)];
+
+        if cycle_length < best_length
+        {
+            best_length = cycle_length;
+            last = j;
+        }
+    }
+
+    if best_length.is_infinite()
+    {
+        return None;
+    }
+
+    // Walks the parents back from the last vertex to vertex 1
+    let mut tour = Vec::with_capacity(vertex_count);
+    let mut subset = full_subset;
+
+    while subset != 0
+    {
+        tour.push(last as u32 + 2);
+
+        let previous = parent[subset * m + last];
+        subset &= !(1 << last);
+        last = previous as usize;
+    }
+
+    tour.push(1);
+    tour.reverse();
+
+    return Some((tour, best_length));
+}
+
+// Bottleneck TSP, searches for the smallest weight such that a hamiltonian cycle exists without any heavier edge by bisecting the sorted weights,
+// then runs Held-Karp on the edges up to that weight, so that among all tours with the smallest bottleneck the shortest one is returned,
+// only edges between the first real_vertices vertices are limited
+pub fn bottleneck_held_karp(
+    adjacency_matrix: &DMatrix<f32>,
+    real_vertices: usize,
+    cancel: &AtomicBool,
+) -> Option<(Vec<u32>, f32)> {
+    let vertex_count = adjacency_matrix.nrows();
+    let is_real_edge = |i: usize, j: usize| i != j && i < real_vertices && j < real_vertices;
+
+    let mut weights: Vec<f32> = (0..vertex_count)
+        .flat_map(|i| (0..vertex_count).map(move |j| (i, j)))
+        .filter(|(i, j)| is_real_edge(*i, *j) && adjacency_matrix[(*i, *j)].is_finite())
+        .map(|edge| adjacency_matrix[edge])
+        .collect();
+
+    weights.sort_by(|a, b| a.total_cmp(b));
+    weights.dedup();
+
+    let limited_matrix = |limit: f32| DMatrix::from_fn(vertex_count, vertex_count, |i, j| {
+        if is_real_edge(i, j) && adjacency_matrix[(i, j)] > limit { f32::INFINITY } else { adjacency_matrix[(i, j)] }
+    });
+
+    let mut best_tour = held_karp(adjacency_matrix, cancel)?;
+    let (mut low, mut high) = (0, weights.len());
+
+    // weights[high] always allows a cycle, high == weights.len() stands for the unlimited graph
+    while low < high
+    {
+        // A cancelled Held-Karp run looks like a graph without a cycle, so the bisection has to stop instead of going on
+        if cancel.load(Ordering::Relaxed)
+        {
+            return None;
+        }
+
+        let middle = (low + high) / 2;
+
+        match held_karp(&limited_matrix(weights[middle]), cancel) {
+            Some(tour) => {
+                best_tour = tour;
+                high = middle;
+            }
+            None => low = middle + 1,
+        }
+    }
+
+    return Some(best_tour);
+}
@@ -1,7 +1,10 @@
 use crate::app;
-use rand_distr::{Distribution, WeightedIndex};
+use crate::dynamic;
+use crate::local_search;
+use crate::vehicle_routing;
+use rand::{rngs::StdRng, Rng, SeedableRng};
 use nalgebra::DMatrix;
-use bevy::{prelude::*, sprite::MaterialMesh2dBundle};
+use bevy::{prelude::*, sprite::MaterialMesh2dBundle, tasks::ComputeTaskPool};
 use bevy_despawn_with::DespawnAllCommandsExt;
 use bevy_prototype_debug_lines::*;
 
@@ -15,6 +18,83 @@ pub struct EditMode{
 pub struct VertexList {
     pub vector: Vec<(u32, f32, f32)>,
     pub count: u32,
+    // Position of every vertex of a 3D instance in space, the vector above then holds their projection onto the canvas,
+    // empty for vertices placed on the canvas
+    pub points: Vec<Vec3>,
+    // Amount of goods every vertex orders in the vehicle routing problem, stored in the order of the vertices
+    pub demands: Vec<u32>,
+    // Time spent on every vertex and the [earliest, latest] arrival time of the time windows, the latest time is infinite for an open window
+    pub service_times: Vec<f32>,
+    pub time_windows: Vec<(f32, f32)>,
+    // Prize collected by visiting the vertex in the selective routing problems, paid as a penalty when the vertex is skipped
+    pub prizes: Vec<f32>,
+    // Cluster of every vertex in the cluster problems, 0 means that the vertex belongs to no cluster
+    pub clusters: Vec<u32>,
+}
+
+// Prize of every new vertex, comparable to the length of an edge on the canvas
+pub const DEFAULT_PRIZE: f32 = 100.0;
+
+// Every list of the vertex list holds one entry per vertex, so vertices are only added and removed through these methods
+impl VertexList {
+    // Appends a vertex at the canvas position with the default values of every problem variant, vertices of 3D instances also get
+    // their point in space, returns the number of the new vertex
+    pub fn push_vertex(&mut self, y: f32, x: f32, point: Option<Vec3>) -> u32 {
+        self.count += 1;
+        self.vector.push((self.count, y, x));
+
+        if let Some(point) = point
+        {
+            self.points.push(point);
+        }
+
+        self.demands.push(1);
+        self.service_times.push(0.0);
+        self.time_windows.push((0.0, f32::INFINITY));
+        self.prizes.push(DEFAULT_PRIZE);
+        self.clusters.push(0);
+
+        return self.count;
+    }
+
+    // Deletes the vertex from every list, the following vertices are numbered one lower
+    pub fn remove_vertex(&mut self, vertex: u32) {
+        let index = vertex as usize - 1;
+
+        self.vector.remove(index);
+
+        if self.points.len() > 0
+        {
+            self.points.remove(index);
+        }
+
+        self.demands.remove(index);
+        self.service_times.remove(index);
+        self.time_windows.remove(index);
+        self.prizes.remove(index);
+        self.clusters.remove(index);
+        self.count -= 1;
+
+        for (number, _, _) in self.vector.iter_mut()
+        {
+            if *number > vertex
+            {
+                *number -= 1;
+            }
+        }
+    }
+
+    // Deletes every vertex
+    pub fn clear_vertices(&mut self) {
+        self.vector = Vec::new();
+        self.count = 0;
+        self.points = Vec::new();
+        self.demands = Vec::new();
+        self.service_times = Vec::new();
+        self.time_windows = Vec::new();
+        self.prizes = Vec::new();
+        self.clusters = Vec::new();
+    }
 }
 
 // Declaration of the edge list that stores edge as tuple in the form of (vertex1, vertex2, distance_between_vertices)
@@ -22,6 +102,17 @@ pub struct VertexList {
 pub struct EdgeList {
     pub vector: Vec<(u32, u32, f32)>,
     pub count: u32,
+    // Manually set weights in the same form, they replace the computed distances and are kept when the graph is rebuilt
+    pub overrides: Vec<(u32, u32, f32)>,
+    // Weights of an instance loaded from a TSPLIB file, indexed like the adjacency matrix, they replace the computed distances
+    // between the loaded vertices, vertices added later get computed ones, the overrides still apply on top
+    pub fixed_weights: Option<DMatrix<f32>>,
+}
+
+// The edge picked by a right click, its weight can be edited in the console
+#[derive(Resource)]
+pub struct EdgeSelection {
+    pub selected: Option<(u32, u32)>,
 }
 
 // Declaration of the adjacency matrix resource that is used to store a given graph as a square matrix
@@ -34,456 +125,2650 @@ pub struct AdjacencyMatrix{
 pub struct ShortestCycle {
     pub vector: Vec<(u32, u32)>,
     pub total_cycle_weight: f32,
+    // Routes of the individual salesmen as (number_of_edges, route_length), their edges follow each other in vector
+    pub routes: Vec<(usize, f32)>,
+    // Vertices left out of the tour by the selective routing problems, they are greyed out
+    pub skipped: Vec<u32>,
+}
+
+// This resource stores which additional layers are drawn on top of the graph
+#[derive(Resource)]
+pub struct DisplaySettings {
+    pub pheromone_overlay: bool,
+}
+
+// Ways of measuring the distance between two vertices
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum DistanceMetric {
+    Euclidean,
+    // Euclidean distance rounded to the nearest integer, the nint function of TSPLIB
+    RoundedEuclidean,
+    Manhattan,
+    Chebyshev,
+    SquaredEuclidean,
+    // Haversine distance in kilometres, the canvas is mapped onto the whole globe with longitude along the width and latitude along the height
+    GreatCircle,
+    // Euclidean distance on a canvas whose opposite sides are glued together
+    Toroidal,
+}
+
+// Radius of the earth used by TSPLIB for GEO instances
+const EARTH_RADIUS: f32 = 6378.388;
+
+// This resource stores how the edge weights are derived from the vertex positions
+#[derive(Resource)]
+pub struct GraphSettings {
+    pub metric: DistanceMetric,
+    // Size of the canvas used by the great-circle and toroidal metrics
+    pub canvas_width: f32,
+    pub canvas_height: f32,
+    // Directed graphs store a separate weight for each direction of an edge
+    pub directed: bool,
+    // Strength of a current circling counter-clockwise around the centre of the canvas, directed edges following the current are cheaper:
+    // weight = distance * (1 - asymmetry * share of the edge direction along the current), use values only from interval <0.0, 1.0)
+    pub asymmetry: f32,
+    // Searches for the shortest hamiltonian path instead of a cycle, optionally with a fixed first and last vertex
+    pub open_path: bool,
+    pub path_start: Option<u32>,
+    pub path_end: Option<u32>,
+    // Number of salesmen that start and end their routes on the depot vertex, every salesman visits at least one other vertex,
+    // more than one salesman takes precedence over the open path mode
+    pub salesmen: u32,
+    pub depot: u32,
+    pub salesmen_objective: SalesmenObjective,
+    pub objective: TourObjective,
+}
+
+// What the solvers optimize, the salesmen objective only applies to the total length
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TourObjective {
+    // Minimize the total length
+    Length,
+    // Minimize the longest edge of the tour, the bottleneck TSP
+    Bottleneck,
+    // Maximize the total length, the max-TSP
+    MaxLength,
+}
+
+// What the salesmen minimize together
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SalesmenObjective {
+    // Total length of all routes
+    MinSum,
+    // Length of the longest route
+    MinMax,
+}
+
+// The instance the solvers actually work on, an open path is solved as a cycle through an extra dummy vertex numbered vertex_count + 1,
+// whose allowed edges all have the same weight, so the cycle is exactly 2 * dummy_weight longer than the path,
+// multiple salesmen are solved as a single cycle through salesmen - 1 copies of the depot numbered from vertex_count + 1,
+// each copy starts a new route and the edges between the copies and the depot are forbidden,
+// the max-TSP is solved as the shortest tour after reflecting the weights, w -> longest + shortest - w, so every solver keeps minimizing
+#[derive(Resource)]
+pub struct SolverGraph {
+    pub matrix: DMatrix<f32>,
+    // Weights of the solver graph before the reflection, the lengths shown to the user are always measured on these
+    pub lengths: DMatrix<f32>,
+    pub directed: bool,
+    pub vertex_count: u32,
+    pub open_path: bool,
+    pub dummy_weight: f32,
+    pub salesmen: u32,
+    pub depot: u32,
+    pub salesmen_objective: SalesmenObjective,
+    pub objective: TourObjective,
+}
+
+// Keeps the solver graph in sync with the adjacency matrix and the problem settings
+pub fn update_solver_graph(
+    graph_settings: Res<GraphSettings>,
+    adjacency_matrix: Res<AdjacencyMatrix>,
+    mut solver_graph: ResMut<SolverGraph>,
+) {
+    if adjacency_matrix.is_changed() || graph_settings.is_changed()
+    {
+        *solver_graph = build_solver_graph(&adjacency_matrix.matrix, &graph_settings);
+    }
+}
+
+pub fn build_solver_graph(
+    adjacency_matrix: &DMatrix<f32>,
+    graph_settings: &GraphSettings,
+) -> SolverGraph {
+    let mut solver_graph = build_length_graph(adjacency_matrix, graph_settings);
+
+    solver_graph.lengths = solver_graph.matrix.clone();
+
+    if graph_settings.objective == TourObjective::MaxLength
+    {
+        solver_graph.matrix = reflect_weights(&solver_graph.lengths);
+    }
+
+    return solver_graph;
+}
+
+// Turns the longest edge into the shortest one and the other way round, forbidden edges stay forbidden,
+// every tour has the same number of edges, so the shortest tour of the reflected weights is the longest tour of the original ones
+fn reflect_weights(
+    adjacency_matrix: &DMatrix<f32>,
+) -> DMatrix<f32> {
+    let vertex_count = adjacency_matrix.nrows();
+    let weights: Vec<f32> = (0..vertex_count)
+        .flat_map(|i| (0..vertex_count).filter(move |j| i != *j).map(move |j| (i, j)))
+        .map(|edge| adjacency_matrix[edge])
+        .filter(|weight| weight.is_finite())
+        .collect();
+
+    let longest = weights.iter().copied().fold(0.0, f32::max);
+    let shortest = weights.iter().copied().fold(longest, f32::min);
+
+    DMatrix::from_fn(vertex_count, vertex_count, |i, j| {
+        if i == j || adjacency_matrix[(i, j)].is_infinite() { adjacency_matrix[(i, j)] } else { longest + shortest - adjacency_matrix[(i, j)] }
+    })
+}
+
+// Builds the solver graph from the original weights
+fn build_length_graph(
+    adjacency_matrix: &DMatrix<f32>,
+    graph_settings: &GraphSettings,
+) -> SolverGraph {
+    let vertex_count = adjacency_matrix.nrows();
+
+    if graph_settings.salesmen > 1 && vertex_count > 0
+    {
+        // A depot that no longer exists falls back to vertex 1
+        let depot = if graph_settings.depot >= 1 && graph_settings.depot as usize <= vertex_count { graph_settings.depot as usize - 1 } else { 0 };
+        let copies = graph_settings.salesmen as usize - 1;
+        let mut matrix = adjacency_matrix.clone().resize(vertex_count + copies, vertex_count + copies, f32::INFINITY);
+
+        for copy in vertex_count..vertex_count + copies
+        {
+            matrix[(copy, copy)] = 0.0;
+
+            for vertex in 0..vertex_count
+            {
+                if vertex != depot
+                {
+                    matrix[(copy, vertex)] = adjacency_matrix[(depot, vertex)];
+                    matrix[(vertex, copy)] = adjacency_matrix[(vertex, depot)];
+                }
+            }
+        }
+
+        return SolverGraph {
+            matrix: matrix,
+            lengths: DMatrix::zeros(0, 0),
+            directed: graph_settings.directed,
+            vertex_count: vertex_count as u32,
+            open_path: false,
+            dummy_weight: 0.0,
+            salesmen: graph_settings.salesmen,
+            depot: depot as u32 + 1,
+            salesmen_objective: graph_settings.salesmen_objective,
+            objective: graph_settings.objective,
+        };
+    }
+
+    if graph_settings.open_path == false || vertex_count == 0
+    {
+        return SolverGraph {
+            matrix: adjacency_matrix.clone(),
+            lengths: DMatrix::zeros(0, 0),
+            directed: graph_settings.directed,
+            vertex_count: vertex_count as u32,
+            open_path: false,
+            dummy_weight: 0.0,
+            salesmen: 1,
+            depot: 1,
+            salesmen_objective: graph_settings.salesmen_objective,
+            objective: graph_settings.objective,
+        };
+    }
+
+    // Fixed vertices that no longer exist are ignored, a path can not start and end on the same vertex
+    let start = graph_settings.path_start.filter(|start| *start >= 1 && *start as usize <= vertex_count).map(|start| start as usize - 1);
+    let end = graph_settings.path_end.filter(|end| *end >= 1 && *end as usize <= vertex_count).map(|end| end as usize - 1).filter(|end| Some(*end) != start);
+
+    // The dummy edges get the average weight, so the heuristic information neither attracts the ants to them nor keeps them away
+    let finite_weights: Vec<f32> = adjacency_matrix.iter().copied().filter(|weight| weight.is_finite() && *weight > 0.0).collect();
+    let dummy_weight = if finite_weights.is_empty() { 1.0 } else { finite_weights.iter().sum::<f32>() / finite_weights.len() as f32 };
+
+    let dummy = vertex_count;
+    let mut matrix = adjacency_matrix.clone().resize(vertex_count + 1, vertex_count + 1, f32::INFINITY);
+
+    matrix[(dummy, dummy)] = 0.0;
+
+    // A fixed end only allows the dummy edges in one direction, so the instance becomes directed
+    let directed = graph_settings.directed || start.is_some() || end.is_some();
+
+    // The cycle enters the dummy vertex from the last vertex of the path and leaves it to the first one,
+    // an undirected cycle may walk through the dummy vertex in either direction
+    for vertex in 0..vertex_count
+    {
+        let first = start.is_none() || start == Some(vertex);
+        let last = end.is_none() || end == Some(vertex);
+
+        if last || (directed == false && first)
+        {
+            matrix[(vertex, dummy)] = dummy_weight;
+        }
+
+        if first || (directed == false && last)
+        {
+            matrix[(dummy, vertex)] = dummy_weight;
+        }
+    }
+
+    SolverGraph {
+        matrix: matrix,
+        lengths: DMatrix::zeros(0, 0),
+        directed: directed,
+        vertex_count: vertex_count as u32,
+        open_path: true,
+        dummy_weight: dummy_weight,
+        salesmen: 1,
+        depot: 1,
+        salesmen_objective: graph_settings.salesmen_objective,
+        objective: graph_settings.objective,
+    }
+}
+
+// Length of the route found by a solver, without the edges of the dummy vertex
+pub fn route_length(
+    tour_length: f32,
+    solver_graph: &SolverGraph,
+) -> f32 {
+    if solver_graph.open_path == true
+    {
+        return tour_length - 2.0 * solver_graph.dummy_weight;
+    }
+
+    return tour_length;
+}
+
+// Splits a tour found by a solver into the routes of the salesmen, every route is a list of vertices that starts and ends on the depot,
+// open paths and single cycles are returned as a single route, the dummy vertex of an open path is left out
+pub fn split_routes(
+    tour: &Vec<u32>,
+    solver_graph: &SolverGraph,
+) -> Vec<Vec<u32>> {
+    if tour.is_empty()
+    {
+        return Vec::new();
+    }
+
+    if solver_graph.open_path == true
+    {
+        // The path starts right after the dummy vertex
+        let mut route = tour.clone();
+
+        if let Some(dummy_index) = route.iter().position(|vertex| *vertex > solver_graph.vertex_count)
+        {
+            route.rotate_left(dummy_index + 1);
+            route.pop();
+        }
+
+        return vec![route];
+    }
+
+    if solver_graph.salesmen < 2
+    {
+        let mut route = tour.clone();
+        route.push(tour[0]);
+
+        return vec![route];
+    }
+
+    let is_depot = |vertex: u32| vertex == solver_graph.depot || vertex > solver_graph.vertex_count;
+    let start = tour.iter().position(|vertex| is_depot(*vertex)).unwrap_or(0);
+
+    let mut routes = Vec::new();
+    let mut route = vec![solver_graph.depot];
+
+    for i in 1..=tour.len()
+    {
+        let vertex = tour[(start + i) % tour.len()];
+
+        if is_depot(vertex)
+        {
+            route.push(solver_graph.depot);
+            routes.push(route);
+            route = vec![solver_graph.depot];
+        } else {
+            route.push(vertex);
+        }
+    }
+
+    return routes;
+}
+
+fn route_weight(
+    route: &Vec<u32>,
+    adjacency_matrix: &DMatrix<f32>,
+) -> f32 {
+    (1..route.len()).map(|i| adjacency_matrix[(route[i - 1] as usize - 1, route[i] as usize - 1)]).sum()
+}
+
+// Weight of the longest edge of the route
+fn longest_edge(
+    route: &Vec<u32>,
+    adjacency_matrix: &DMatrix<f32>,
+) -> f32 {
+    (1..route.len()).map(|i| adjacency_matrix[(route[i - 1] as usize - 1, route[i] as usize - 1)]).fold(0.0, f32::max)
+}
+
+// The value the solvers minimize, the total tour length unless the salesmen minimize their longest route or the tour its longest edge,
+// the max-TSP minimizes the length of the reflected weights
+pub fn tour_cost(
+    tour: &Vec<u32>,
+    tour_length: f32,
+    solver_graph: &SolverGraph,
+) -> f32 {
+    // The edges of the dummy vertex are left out by splitting the tour into routes, so they never become the bottleneck
+    if solver_graph.objective == TourObjective::Bottleneck && tour_length.is_finite()
+    {
+        return split_routes(tour, solver_graph).iter().map(|route| longest_edge(route, &solver_graph.lengths)).fold(0.0, f32::max);
+    }
+
+    if solver_graph.objective == TourObjective::Length && solver_graph.salesmen > 1 && solver_graph.salesmen_objective == SalesmenObjective::MinMax && tour_length.is_finite()
+    {
+        return split_routes(tour, solver_graph).iter().map(|route| route_weight(route, &solver_graph.lengths)).fold(0.0, f32::max);
+    }
+
+    return tour_length;
+}
+
+// Stores a tour found by a solver as the list of edges that are drawn, open paths are drawn without the closing edge
+pub fn store_shortest_cycle(
+    tour: &Vec<u32>,
+    tour_length: f32,
+    solver_graph: &SolverGraph,
+    shortest_cycle: &mut ShortestCycle,
+) {
+    shortest_cycle.vector = Vec::new();
+    shortest_cycle.total_cycle_weight = 0.0;
+    shortest_cycle.routes = Vec::new();
+    shortest_cycle.skipped = Vec::new();
+
+    // Tours using a forbidden edge are not drawn
+    if tour_length.is_infinite() || tour.is_empty()
+    {
+        return;
+    }
+
+    for route in split_routes(tour, solver_graph)
+    {
+        let length = route_weight(&route, &solver_graph.lengths);
+
+        for i in 1..route.len()
+        {
+            shortest_cycle.vector.push((route[i - 1], route[i]));
+        }
+
+        shortest_cycle.routes.push((route.len() - 1, length));
+        shortest_cycle.total_cycle_weight += length;
+    }
+}
+
+// Lists the length of every route when there is more than one salesman
+pub fn route_summary(
+    shortest_cycle: &ShortestCycle,
+) -> String {
+    if shortest_cycle.routes.len() < 2
+    {
+        return String::new();
+    }
+
+    let lengths: Vec<String> = shortest_cycle.routes.iter().enumerate().map(|(index, route)| format!("{}: {:.1}", index + 1, route.1)).collect();
+    let longest = shortest_cycle.routes.iter().map(|route| route.1).fold(0.0, f32::max);
+
+    format!(
+        "\nRoutes {} (longest {:.1}, total {:.1})",
+        lengths.join(", "),
+        longest,
+        shortest_cycle.total_cycle_weight,
+    )
+}
+
+// Value of the objective of a tour rated by tour_cost, measured on the original weights
+pub fn objective_value(
+    tour: &Vec<u32>,
+    cost: f32,
+    solver_graph: &SolverGraph,
+) -> f32 {
+    match solver_graph.objective {
+        TourObjective::Length => route_length(cost, solver_graph),
+        TourObjective::Bottleneck => cost,
+        TourObjective::MaxLength => split_routes(tour, solver_graph).iter().map(|route| route_weight(route, &solver_graph.lengths)).sum(),
+    }
 }
 
+// The heaviest edge of the stored tour as (vertex1, vertex2, weight)
+pub fn bottleneck_edge(
+    shortest_cycle: &ShortestCycle,
+    adjacency_matrix: &DMatrix<f32>,
+) -> Option<(u32, u32, f32)> {
+    shortest_cycle.vector.iter()
+        .map(|(vertex1, vertex2)| (*vertex1, *vertex2, adjacency_matrix[(*vertex1 as usize - 1, *vertex2 as usize - 1)]))
+        .max_by(|a, b| a.2.total_cmp(&b.2))
+}
+
+// Reports the objective when the solvers do not minimize the total length
+pub fn objective_summary(
+    shortest_cycle: &ShortestCycle,
+    solver_graph: &SolverGraph,
+) -> String {
+    match solver_graph.objective {
+        TourObjective::Length => String::new(),
+        TourObjective::Bottleneck => match bottleneck_edge(shortest_cycle, &solver_graph.lengths) {
+            Some((vertex1, vertex2, weight)) => format!("\nBottleneck edge {} - {} with weight {:.1}, tour length {:.1}", vertex1, vertex2, weight, shortest_cycle.total_cycle_weight),
+            None => String::new(),
+        },
+        TourObjective::MaxLength => format!("\nLongest tour length {:.1}", shortest_cycle.total_cycle_weight),
+    }
+}
+
+// Colour of the route of the given salesman
+pub fn route_color(
+    index: usize,
+) -> Color {
+    let palette = [
+        Color::WHITE,
+        Color::rgb(0.3, 0.8, 1.0),
+        Color::rgb(1.0, 0.45, 0.35),
+        Color::rgb(0.5, 1.0, 0.4),
+        Color::rgb(1.0, 0.85, 0.2),
+        Color::rgb(0.85, 0.45, 1.0),
+        Color::rgb(1.0, 0.6, 0.8),
+        Color::rgb(0.4, 1.0, 0.85),
+    ];
+
+    palette[index % palette.len()]
+}
 
 // Waits for user input and stores it as a graph accordingly
 pub fn graph_handler(
     mouse_button_input: Res<Input<MouseButton>>,
+    keyboard_input: Res<Input<KeyCode>>,
     window: Query<&mut Window>,
     edit_mode: Res<EditMode>,
+    graph_settings: Res<GraphSettings>,
     mut adjacency_matrix: ResMut<AdjacencyMatrix>,
+    solver_graph: Res<SolverGraph>,
     mut vertex_list: ResMut<VertexList>,
     mut edge_list: ResMut<EdgeList>,
+    mut edge_selection: ResMut<EdgeSelection>,
+    mut dynamic_graph: ResMut<dynamic::DynamicGraph>,
+    mut triangle_violation: Local<Option<(u32, u32, u32)>>,
+    mut hamiltonicity_obstruction: Local<Option<String>>,
     mut info_text_param_set: ParamSet<(
         Query<&mut Text, With<app::VertexCountText>>,
         Query<&mut Text, With<app::EdgeCountText>>,
         Query<&mut Text, With<app::PossibleCyclesText>>,
+        Query<&mut Text, With<app::GraphWarningText>>,
     )>,
 ) {
     if edit_mode.activate == true
     {
         let win = window.single();
         let count: u32;
-        let mut possible_cycles_count: u128 = 1;
-
-        let mut x1: f32;
-        let mut y1: f32;
 
-        let mut x2: f32;
-        let mut y2: f32;
+        // Holding shift drags an existing vertex instead, see dynamic::edit_vertices
+        let shift = keyboard_input.pressed(KeyCode::LShift) || keyboard_input.pressed(KeyCode::RShift);
 
-        let mut distance_x: f32;
-        let mut distance_y: f32;
+        // The mouse orbits the camera around 3D instances, see view_3d::orbit_camera
+        let instance_3d = vertex_list.points.len() > 0;
 
-        let mut temp: f32;
-
-        if mouse_button_input.just_pressed(MouseButton::Left) {
-            vertex_list.count += 1;
-            count = vertex_list.count;
-            vertex_list.vector.push((
-                count,
+        if mouse_button_input.just_pressed(MouseButton::Left) && shift == false && instance_3d == false {
+            count = vertex_list.push_vertex(
                 app::get_cursor_position(win).y - (win.height() / 2.),
                 app::get_cursor_position(win).x - (win.width() / 2.),
-            ));
+                None,
+            );
             println!(
                 "new vertex number: {:?}",
                 vertex_list.vector[(count - 1) as usize].0
             );
 
-            for mut vertex_count_text in &mut info_text_param_set.p0().iter_mut() {
-                vertex_count_text.sections[0].value =
-                    format!("Number of vertices: {}", vertex_list.count.to_string());
+            rebuild_graph(&vertex_list, &mut edge_list, &mut adjacency_matrix, &graph_settings);
+
+            dynamic_graph.changes.push(dynamic::GraphChange::Added(count));
+            dynamic_graph.vertex_count = count;
+        }
+
+        // Right click picks the nearest edge so that its weight can be typed into the console
+        if mouse_button_input.just_pressed(MouseButton::Right) && instance_3d == false {
+            let cursor = Vec2::new(
+                app::get_cursor_position(win).x - (win.width() / 2.),
+                app::get_cursor_position(win).y - (win.height() / 2.),
+            );
+
+            edge_selection.selected = nearest_edge(&vertex_list, &edge_list, cursor, 10.0);
+        }
+    }
+
+    // Checking the triangle inequality takes cubic time, so it only runs when the weights change
+    if adjacency_matrix.is_changed() || solver_graph.is_changed()
+    {
+        *triangle_violation = if may_violate_triangle_inequality(&edge_list, &graph_settings) {
+            find_triangle_violation(&adjacency_matrix.matrix)
+        } else {
+            None
+        };
+
+        if let Some((vertex1, vertex2, vertex3)) = *triangle_violation
+        {
+            println!("warning: the graph is not metric, the path {} - {} - {} is shorter than the edge {} - {}", vertex1, vertex2, vertex3, vertex1, vertex3);
+        }
+
+        *hamiltonicity_obstruction = if has_forbidden_edges(&edge_list) {
+            find_hamiltonicity_obstruction(&solver_graph.matrix, solver_graph.directed)
+        } else {
+            None
+        };
+
+        if let Some(reason) = &*hamiltonicity_obstruction
+        {
+            println!("warning: the graph has no hamiltonian cycle, {}", reason);
+        }
+    }
+
+    let mut warnings = Vec::new();
+
+    if let Some((vertex1, vertex2, vertex3)) = *triangle_violation
+    {
+        warnings.push(format!(
+            "Non-metric graph: path {} - {} - {} is shorter than edge {} - {}\nnearest neighbour lists and local search may perform poorly",
+            vertex1, vertex2, vertex3, vertex1, vertex3,
+        ));
+    }
+
+    if let Some(reason) = &*hamiltonicity_obstruction
+    {
+        warnings.push(format!("No hamiltonian {} exists: {}", if solver_graph.open_path { "path" } else { "cycle" }, reason));
+    }
+
+    for mut graph_warning_text in &mut info_text_param_set.p3().iter_mut()
+    {
+        graph_warning_text.sections[0].value = warnings.join("\n");
+    }
+
+    let forbidden_edges = edge_list.vector.iter().filter(|(_, _, weight)| weight.is_infinite()).count() as u32;
+
+    // The texts are refreshed on every frame, because the console can change the graph as well
+    for mut vertex_count_text in &mut info_text_param_set.p0().iter_mut() {
+        vertex_count_text.sections[0].value =
+            format!("Number of vertices: {}", vertex_list.count.to_string());
+    }
+
+    for mut edge_count_text in &mut info_text_param_set.p1().iter_mut() {
+        edge_count_text.sections[0].value =
+            format!("Number of edges: {}", (edge_list.count - forbidden_edges).to_string())
+    }
+
+    // Paths are counted as cycles through the dummy vertex, with a fixed end they have a direction and every fixed end removes one choice
+    let fixed_ends = graph_settings.path_start.is_some() as u32 + graph_settings.path_end.is_some() as u32;
+
+    let (route_name, possible_routes) = match (graph_settings.open_path, fixed_ends) {
+        (false, _) => ("cycles", possible_cycles(vertex_list.count, graph_settings.directed)),
+        (true, 0) => ("paths", possible_cycles(vertex_list.count + 1, graph_settings.directed)),
+        (true, _) => ("paths", possible_cycles((vertex_list.count + 2).saturating_sub(fixed_ends), true)),
+    };
+
+    for mut possible_cycles_text in &mut info_text_param_set.p2().iter_mut()
+    {
+        possible_cycles_text.sections[0].value = if hamiltonicity_obstruction.is_some() {
+            format!("Number of possible {}: 0", route_name)
+        } else if forbidden_edges > 0 {
+            format!("Number of possible {}: at most {}", route_name, possible_routes)
+        } else {
+            format!("Number of possible {}: {}", route_name, possible_routes)
+        };
+    }
+}
+
+// Returns the edge closest to the given canvas position, if it is not further away than max_distance
+fn nearest_edge(
+    vertex_list: &VertexList,
+    edge_list: &EdgeList,
+    position: Vec2,
+    max_distance: f32,
+) -> Option<(u32, u32)> {
+    let mut nearest = None;
+    let mut nearest_distance = max_distance;
+
+    for (vertex1, vertex2, _) in edge_list.vector.iter().filter(|(_, _, weight)| weight.is_finite())
+    {
+        let (_, y1, x1) = vertex_list.vector[*vertex1 as usize - 1];
+        let (_, y2, x2) = vertex_list.vector[*vertex2 as usize - 1];
+
+        let start = Vec2::new(x1, y1);
+        let segment = Vec2::new(x2, y2) - start;
+        let t = ((position - start).dot(segment) / segment.length_squared().max(f32::EPSILON)).clamp(0.0, 1.0);
+        let distance = position.distance(start + segment * t);
+
+        if distance <= nearest_distance
+        {
+            nearest_distance = distance;
+            nearest = Some((*vertex1.min(vertex2), *vertex1.max(vertex2)));
+        }
+    }
+
+    return nearest;
+}
+
+// The distance metrics never make a detour shorter than the direct edge, only overrides, squared distances and the current in directed graphs can,
+// so the cubic search for a violation is skipped for all other graphs
+pub fn may_violate_triangle_inequality(
+    edge_list: &EdgeList,
+    graph_settings: &GraphSettings,
+) -> bool {
+    edge_list.overrides.len() > 0
+        || graph_settings.metric == DistanceMetric::SquaredEuclidean
+        || (graph_settings.directed == true && graph_settings.asymmetry > 0.0)
+}
+
+// Finds vertices a, b, c such that going from a to c through b is shorter than the direct edge, returns None when the graph is metric,
+// forbidden edges are ignored
+pub fn find_triangle_violation(
+    adjacency_matrix: &DMatrix<f32>,
+) -> Option<(u32, u32, u32)> {
+    let vertex_count = adjacency_matrix.nrows();
+
+    for a in 0..vertex_count
+    {
+        for c in 0..vertex_count
+        {
+            if a == c
+            {
+                continue;
             }
 
-            edge_list.count += count - 1;
+            for b in 0..vertex_count
+            {
+                if b == a || b == c
+                {
+                    continue;
+                }
+
+                // A small tolerance keeps the rounding of the distances from raising false alarms
+                if adjacency_matrix[(a, c)].is_finite() && adjacency_matrix[(a, b)] + adjacency_matrix[(b, c)] < adjacency_matrix[(a, c)] * 0.9999
+                {
+                    return Some((a as u32 + 1, b as u32 + 1, c as u32 + 1));
+                }
+            }
+        }
+    }
 
-            if count > 1 {
-                for i in 1..count {
-                    x1 = vertex_list.vector[(count - 1) as usize].2;
-                    y1 = vertex_list.vector[(count - 1) as usize].1;
+    return None;
+}
 
-                    x2 = vertex_list.vector[(i - 1) as usize].2;
-                    y2 = vertex_list.vector[(i - 1) as usize].1;
+// A complete graph always has a hamiltonian cycle, so the search for an obstruction, which takes cubic time, is only needed once an edge is forbidden
+pub fn has_forbidden_edges(
+    edge_list: &EdgeList,
+) -> bool {
+    edge_list.vector.iter().any(|(_, _, weight)| weight.is_infinite())
+}
 
-                    if x2 < x1 {
-                        temp = x1;
-                        x1 = x2;
-                        x2 = temp;
+// Largest graph for which the existence of a hamiltonian cycle is decided by an exhaustive search
+const HAMILTONICITY_SEARCH_LIMIT: usize = 16;
 
-                        temp = y1;
-                        y1 = y2;
-                        y2 = temp;
-                    }
+// Looks for a reason why the graph can not contain a hamiltonian cycle when some edges are forbidden: a vertex with too few allowed edges,
+// a disconnected graph, a cut vertex, or for small graphs an exhaustive search, returns None when no obstruction was found
+pub fn find_hamiltonicity_obstruction(
+    adjacency_matrix: &DMatrix<f32>,
+    directed: bool,
+) -> Option<String> {
+    let vertex_count = adjacency_matrix.nrows();
+
+    if vertex_count < 3
+    {
+        return None;
+    }
+
+    let allowed = |from: usize, to: usize| from != to && adjacency_matrix[(from, to)].is_finite();
+
+    for vertex in 0..vertex_count
+    {
+        let outgoing = (0..vertex_count).filter(|other| allowed(vertex, *other)).count();
+        let incoming = (0..vertex_count).filter(|other| allowed(*other, vertex)).count();
+
+        if directed == false && outgoing < 2
+        {
+            return Some(format!("vertex {} has only {} allowed edges", vertex + 1, outgoing));
+        }
+
+        if directed == true && (outgoing == 0 || incoming == 0)
+        {
+            return Some(format!("vertex {} has no allowed {} edge", vertex + 1, if outgoing == 0 { "outgoing" } else { "incoming" }));
+        }
+    }
+
+    // In directed graphs every vertex has to be reachable from vertex 1 and vertex 1 from every vertex
+    let reached = reachable_vertices(vertex_count, None, |from, to| allowed(from, to));
+    let reached_backwards = reachable_vertices(vertex_count, None, |from, to| allowed(to, from));
+
+    for vertex in 0..vertex_count
+    {
+        if reached[vertex] == false || reached_backwards[vertex] == false
+        {
+            return Some(format!("the graph is not connected, vertex {} is cut off from vertex 1", vertex + 1));
+        }
+    }
+
+    // A hamiltonian cycle stays connected after removing any single vertex
+    if directed == false
+    {
+        for removed in 0..vertex_count
+        {
+            let reached = reachable_vertices(vertex_count, Some(removed), |from, to| allowed(from, to));
+
+            if (0..vertex_count).any(|vertex| vertex != removed && reached[vertex] == false)
+            {
+                return Some(format!("removing vertex {} disconnects the graph", removed + 1));
+            }
+        }
+    }
+
+    if vertex_count <= HAMILTONICITY_SEARCH_LIMIT && hamiltonian_cycle_exists(vertex_count, |from, to| allowed(from, to)) == false
+    {
+        return Some("exhaustive search found no hamiltonian cycle".to_string());
+    }
+
+    return None;
+}
+
+// Breadth first search over the allowed edges, starting on the first vertex that was not removed
+fn reachable_vertices<F: Fn(usize, usize) -> bool>(
+    vertex_count: usize,
+    removed: Option<usize>,
+    allowed: F,
+) -> Vec<bool> {
+    let start = if removed == Some(0) { 1 } else { 0 };
+    let mut reached = vec![false; vertex_count];
+    let mut queue = vec![start];
+
+    reached[start] = true;
+
+    while let Some(vertex) = queue.pop()
+    {
+        for other in 0..vertex_count
+        {
+            if reached[other] == false && Some(other) != removed && allowed(vertex, other)
+            {
+                reached[other] = true;
+                queue.push(other);
+            }
+        }
+    }
+
+    return reached;
+}
+
+// Dynamic programming over subsets, ends[subset] holds one bit for every vertex on which a path from vertex 1 through exactly the subset can end
+fn hamiltonian_cycle_exists<F: Fn(usize, usize) -> bool>(
+    vertex_count: usize,
+    allowed: F,
+) -> bool {
+    let successors: Vec<u32> = (0..vertex_count)
+        .map(|vertex| (0..vertex_count).filter(|other| allowed(vertex, *other)).fold(0, |bits, other| bits | 1 << other))
+        .collect();
+
+    let full_subset = (1usize << vertex_count) - 1;
+    let mut ends = vec![0u32; full_subset + 1];
+
+    ends[1] = 1;
+
+    // Every path contains vertex 1, so only odd subsets are visited
+    for subset in (1..=full_subset).step_by(2)
+    {
+        let mut remaining_ends = ends[subset];
+
+        while remaining_ends != 0
+        {
+            let end = remaining_ends.trailing_zeros() as usize;
+            let mut next_vertices = successors[end] & !(subset as u32);
+
+            remaining_ends &= remaining_ends - 1;
+
+            while next_vertices != 0
+            {
+                let next = next_vertices.trailing_zeros() as usize;
+
+                next_vertices &= next_vertices - 1;
+                ends[subset | 1 << next] |= 1 << next;
+            }
+        }
+    }
+
+    return (0..vertex_count).any(|end| ends[full_subset] & 1 << end != 0 && allowed(end, 0));
+}
+
+// Whether the edge stored as (edge_vertex1, edge_vertex2) connects vertex1 to vertex2, in undirected graphs the order does not matter
+pub fn same_edge(
+    edge_vertex1: u32,
+    edge_vertex2: u32,
+    vertex1: u32,
+    vertex2: u32,
+    directed: bool,
+) -> bool {
+    (edge_vertex1 == vertex1 && edge_vertex2 == vertex2) || (directed == false && edge_vertex1 == vertex2 && edge_vertex2 == vertex1)
+}
+
+// Number of distinct hamiltonian cycles in a complete graph, (n - 1)! / 2 for undirected and (n - 1)! for directed graphs
+fn possible_cycles(
+    count: u32,
+    directed: bool,
+) -> String {
+    let mut possible_cycles_count: Option<u128> = Some(1);
+    let mut approximate_count: f64 = 1.0;
+
+    for i in 1..count {
+        possible_cycles_count = possible_cycles_count.and_then(|possible_cycles_count| possible_cycles_count.checked_mul((count - i) as u128));
+        approximate_count *= (count - i) as f64;
+    }
+
+    let divisor = if directed { 1 } else { 2 };
+
+    match possible_cycles_count {
+        Some(possible_cycles_count) => format!("{}", possible_cycles_count / divisor),
+        None => format!("{:.3e}", approximate_count / divisor as f64),
+    }
+}
+
+// Weight of the edge leading from vertex1 to vertex2
+pub fn edge_weight(
+    vertex_list: &VertexList,
+    vertex1: u32,
+    vertex2: u32,
+    graph_settings: &GraphSettings,
+) -> f32 {
+    let (point1, point2) = (model_position(vertex_list, vertex1), model_position(vertex_list, vertex2));
+    let (x1, y1, x2, y2) = (point1.x, point1.y, point2.x, point2.y);
+
+    let distance = metric_distance(point1, point2, graph_settings);
+
+    if graph_settings.directed == true && (x1 != x2 || y1 != y2)
+    {
+        // Direction of the current at the midpoint of the edge, an edge running through the centre is not affected
+        let midpoint = Vec2::new(x1 + x2, y1 + y2) / 2.;
+        let current = Vec2::new(-midpoint.y, midpoint.x).normalize_or_zero();
+        let along_current = current.dot(Vec2::new(x2 - x1, y2 - y1).normalize());
+
+        return distance * (1.0 - graph_settings.asymmetry * along_current);
+    }
+
+    return distance;
+}
+
+// Position of the vertex the weights are measured from, the point in space of 3D instances and the canvas position otherwise
+fn model_position(
+    vertex_list: &VertexList,
+    vertex: u32,
+) -> Vec3 {
+    if vertex_list.points.len() == vertex_list.vector.len()
+    {
+        if let Some(point) = vertex_list.points.get(vertex as usize - 1)
+        {
+            return *point;
+        }
+    }
+
+    let (_, y, x) = vertex_list.vector[vertex as usize - 1];
+
+    Vec3::new(x, y, 0.)
+}
+
+// Distance between two points according to the selected metric, canvas positions lie in the plane z = 0,
+// the great-circle and toroidal metrics only consider the canvas coordinates
+fn metric_distance(
+    point1: Vec3,
+    point2: Vec3,
+    graph_settings: &GraphSettings,
+) -> f32 {
+    let dx = (point2.x - point1.x).abs();
+    let dy = (point2.y - point1.y).abs();
+    let dz = (point2.z - point1.z).abs();
+
+    match graph_settings.metric {
+        DistanceMetric::Euclidean => (dx.powf(2.) + dy.powf(2.) + dz.powf(2.)).sqrt(),
+        DistanceMetric::RoundedEuclidean => ((dx.powf(2.) + dy.powf(2.) + dz.powf(2.)).sqrt() + 0.5).floor(),
+        DistanceMetric::Manhattan => dx + dy + dz,
+        DistanceMetric::Chebyshev => dx.max(dy).max(dz),
+        DistanceMetric::SquaredEuclidean => dx.powf(2.) + dy.powf(2.) + dz.powf(2.),
+        DistanceMetric::GreatCircle => {
+            let longitude1 = (point1.x / (graph_settings.canvas_width / 2.) * 180.).to_radians();
+            let longitude2 = (point2.x / (graph_settings.canvas_width / 2.) * 180.).to_radians();
+            let latitude1 = (point1.y / (graph_settings.canvas_height / 2.) * 90.).to_radians();
+            let latitude2 = (point2.y / (graph_settings.canvas_height / 2.) * 90.).to_radians();
+
+            let haversine = ((latitude2 - latitude1) / 2.).sin().powf(2.)
+                + latitude1.cos() * latitude2.cos() * ((longitude2 - longitude1) / 2.).sin().powf(2.);
+
+            2. * EARTH_RADIUS * haversine.sqrt().min(1.0).asin()
+        }
+        DistanceMetric::Toroidal => {
+            let dx = dx.min(graph_settings.canvas_width - dx);
+            let dy = dy.min(graph_settings.canvas_height - dy);
+
+            (dx.powf(2.) + dy.powf(2.)).sqrt()
+        }
+    }
+}
+
+// Recomputes the edge list and the adjacency matrix of the complete graph from the vertex positions
+pub fn rebuild_graph(
+    vertex_list: &VertexList,
+    edge_list: &mut EdgeList,
+    adjacency_matrix: &mut AdjacencyMatrix,
+    graph_settings: &GraphSettings,
+) {
+    let count = vertex_list.count;
+
+    let mut edges = Vec::new();
+    adjacency_matrix.matrix = DMatrix::from_diagonal_element(count as usize, count as usize, 0.0);
+
+    let weight = |vertex1: u32, vertex2: u32| match &edge_list.fixed_weights {
+        Some(weights) if vertex1 as usize <= weights.nrows() && vertex2 as usize <= weights.nrows() => weights[(vertex1 as usize - 1, vertex2 as usize - 1)],
+        _ => edge_weight(vertex_list, vertex1, vertex2, graph_settings),
+    };
+
+    for j in 2..=count
+    {
+        for i in 1..j
+        {
+            edges.push((j, i, weight(j, i)));
+
+            if graph_settings.directed == true
+            {
+                edges.push((i, j, weight(i, j)));
+            }
+        }
+    }
+
+    edge_list.vector = edges;
+
+    // Overrides of vertices that no longer exist are dropped
+    edge_list.overrides.retain(|(vertex1, vertex2, _)| *vertex1 <= count && *vertex2 <= count);
+
+    for edge in edge_list.vector.iter_mut()
+    {
+        for (vertex1, vertex2, weight) in &edge_list.overrides
+        {
+            if same_edge(*vertex1, *vertex2, edge.0, edge.1, graph_settings.directed)
+            {
+                edge.2 = *weight;
+            }
+        }
+    }
+
+    edge_list.count = edge_list.vector.len() as u32;
+
+    // The adjacency matrix stores the weight of the edge leading from the row vertex to the column vertex
+    for i in 0..edge_list.count
+    {
+        adjacency_matrix.matrix[(edge_list.vector[i as usize].0 as usize - 1 ,edge_list.vector[i as usize].1 as usize - 1)] = edge_list.vector[i as usize].2;
+
+        if graph_settings.directed == false
+        {
+            adjacency_matrix.matrix[(edge_list.vector[i as usize].1 as usize - 1, edge_list.vector[i as usize].0 as usize - 1)] = edge_list.vector[i as usize].2;
+        }
+    }
+}
+
+#[derive(Component)]
+struct Vertex;
+
+#[derive(Component)]
+struct VertexNumber;
+
+#[derive(Component)]
+struct Edge;
+
+#[derive(Component)]
+struct PheromoneLegend;
+
+#[derive(Component)]
+struct EdgeWeightLabel;
+
+// This function draws the graph on the canvas on every new frame
+pub fn draw_graph(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut lines: ResMut<DebugLines>,
+    asset_server: Res<AssetServer>,
+    window: Query<&mut Window>,
+    vertex_list: ResMut<VertexList>,
+    edge_list: ResMut<EdgeList>,
+    edge_selection: Res<EdgeSelection>,
+    shortest_cycle: ResMut<ShortestCycle>,
+    display_settings: Res<DisplaySettings>,
+    graph_settings: Res<GraphSettings>,
+    ant_colony_parameters: Res<AntColonyParameters>,
+    multi_colony_parameters: Res<MultiColonyParameters>,
+    vehicle_routing: Res<vehicle_routing::VehicleRouting>,
+) {
+    let mut x1: f32;
+    let mut y1: f32;
+
+    let mut x2: f32;
+    let mut y2: f32;
+
+    let win = window.single();
+
+    commands.despawn_all::<With<Vertex>>();
+    commands.despawn_all::<With<VertexNumber>>();
+    commands.despawn_all::<With<PheromoneLegend>>();
+    commands.despawn_all::<With<EdgeWeightLabel>>();
+
+    for i in 0..edge_list.count
+    {
+        x1 = vertex_list.vector[(edge_list.vector[(i as usize)].0 - 1) as usize].2;
+        y1 = vertex_list.vector[(edge_list.vector[(i as usize)].0 - 1) as usize].1;
+
+        x2 = vertex_list.vector[(edge_list.vector[(i as usize)].1 - 1) as usize].2;
+        y2 = vertex_list.vector[(edge_list.vector[(i as usize)].1 - 1) as usize].1;
+
+        // Forbidden edges are not part of the graph anymore
+        if edge_list.vector[i as usize].2.is_infinite()
+        {
+            continue;
+        }
+
+        lines.line_colored(
+            Vec3::new(x1, y1, 0.),
+            Vec3::new(x2, y2, 0.),
+            0.0,
+            Color::BLACK,
+        );
+    }
+
+    // Manually set weights are drawn in orange together with their value, the selected edge in yellow
+    for (vertex1, vertex2, weight) in edge_list.overrides.iter().filter(|(_, _, weight)| weight.is_finite())
+    {
+        let (_, y1, x1) = vertex_list.vector[*vertex1 as usize - 1];
+        let (_, y2, x2) = vertex_list.vector[*vertex2 as usize - 1];
+
+        draw_thick_line(&mut lines, Vec3::new(x1, y1, 0.), Vec3::new(x2, y2, 0.), Color::ORANGE, 3);
+
+        // In directed graphs the label is shifted towards the end the edge leads to, so both directions stay readable
+        let label_position = if graph_settings.directed == true {
+            Vec2::new(x1 + (x2 - x1) * 0.7, y1 + (y2 - y1) * 0.7)
+        } else {
+            Vec2::new(x1 + x2, y1 + y2) / 2.
+        };
+
+        commands.spawn((
+            TextBundle::from_section(
+                format!("{}", weight),
+                TextStyle {
+                    font: asset_server.load("fonts/FiraSans-ExtraLight.ttf"),
+                    font_size: 20.0,
+                    color: Color::ORANGE,
+                },
+            )
+                .with_text_alignment(TextAlignment::Left)
+                .with_style(Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        bottom: Val::Px(label_position.y + (win.height() / 2.) + 5.),
+                        left: Val::Px(label_position.x + (win.width() / 2.) + 5.),
+                        ..default()
+                    },
+                    ..default()
+                }),
+            EdgeWeightLabel,
+        ));
+    }
+
+    if let Some((vertex1, vertex2)) = edge_selection.selected
+    {
+        let (_, y1, x1) = vertex_list.vector[vertex1 as usize - 1];
+        let (_, y2, x2) = vertex_list.vector[vertex2 as usize - 1];
+
+        draw_thick_line(&mut lines, Vec3::new(x1, y1, 0.), Vec3::new(x2, y2, 0.), Color::YELLOW, 3);
+    }
+
+    if display_settings.pheromone_overlay == true
+    {
+        // The multi-colony mode shows the average of all pheromone matrices
+        let pheromone_matrix = if multi_colony_parameters.activate == true {
+            let mut average_pheromone_matrix = multi_colony_parameters.colonies[0].pheromone_matrix.clone() * 0.0;
+
+            for colony in &multi_colony_parameters.colonies
+            {
+                average_pheromone_matrix += &colony.pheromone_matrix;
+            }
+
+            average_pheromone_matrix / multi_colony_parameters.colonies.len() as f32
+        } else {
+            ant_colony_parameters.pheromone_matrix.clone()
+        };
+
+        draw_pheromone_overlay(
+            &mut commands,
+            &mut lines,
+            &asset_server,
+            win,
+            &vertex_list,
+            &pheromone_matrix,
+        );
+    }
+
+    // Every salesman has its own colour, the edges of the routes follow each other in the vector
+    let mut edge_colors = Vec::with_capacity(shortest_cycle.vector.len());
+
+    for (route, (number_of_edges, _)) in shortest_cycle.routes.iter().enumerate()
+    {
+        edge_colors.extend(std::iter::repeat(route_color(route)).take(*number_of_edges));
+    }
+
+    for i in 0..shortest_cycle.vector.len()
+    {
+        x1 = vertex_list.vector[(shortest_cycle.vector[(i as usize)].0 - 1) as usize].2;
+        y1 = vertex_list.vector[(shortest_cycle.vector[(i as usize)].0 - 1) as usize].1;
+
+        x2 = vertex_list.vector[(shortest_cycle.vector[(i as usize)].1 - 1) as usize].2;
+        y2 = vertex_list.vector[(shortest_cycle.vector[(i as usize)].1 - 1) as usize].1;
+
+        let color = edge_colors.get(i).copied().unwrap_or(Color::WHITE);
+
+        lines.line_colored(
+            Vec3::new(x1, y1, 0.),
+            Vec3::new(x2, y2, 0.),
+            0.0,
+            color,
+        );
+
+        // In directed graphs the direction in which the cycle is walked matters
+        if graph_settings.directed == true
+        {
+            draw_arrow_head(&mut lines, Vec3::new(x1, y1, 0.), Vec3::new(x2, y2, 0.), color);
+        }
+    }
+
+    for i in 0..vertex_list.count {
+        // Vertices skipped by the selective routing problems are greyed out
+        let skipped = shortest_cycle.skipped.contains(&(i + 1));
+
+        commands
+            .spawn(MaterialMesh2dBundle {
+                mesh: meshes.add(shape::Circle::new(15.).into()).into(),
+                // The depot of the salesmen and of the vehicles is drawn in red
+                material: materials.add(ColorMaterial::from(if (graph_settings.salesmen > 1 && graph_settings.depot == i + 1) || (vehicle_routing.routes.len() > 0 && vehicle_routing.depot == i + 1) {
+                    Color::MAROON
+                } else if skipped == true {
+                    Color::DARK_GRAY
+                } else {
+                    Color::BLACK
+                })),
+                transform: Transform::from_translation(Vec3::new(
+                    vertex_list.vector[(i as usize)].2,
+                    vertex_list.vector[(i as usize)].1,
+                    0.,
+                )),
+                ..default()
+            })
+            .insert(Vertex);
+
+        commands.spawn((
+            // Create a TextBundle that has a Text with a single section.
+            TextBundle::from_section(
+                // Accepts a `String` or any type that converts into a `String`, such as `&str`
+                vertex_list.vector[i as usize].0.to_string(),
+                TextStyle {
+                    font: asset_server.load("fonts/FiraSans-ExtraLight.ttf"),
+                    font_size: 30.0,
+                    color: if skipped == true { Color::GRAY } else { Color::WHITE },
+                },
+            ) // Set the alignment of the Text
+                .with_text_alignment(TextAlignment::Left)
+                // Set the style of the TextBundle itself.
+                .with_style(Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        bottom: Val::Px(vertex_list.vector[(i as usize)].1 + (win.height() / 2.) - 50.),
+                        left: Val::Px(vertex_list.vector[(i as usize)].2 + (win.width() / 2.) - 5.),
+                        ..default()
+                    },
+                    ..default()
+                }),
+            VertexNumber,
+        ));
+    }
+}
+
+// This system highlights the heaviest edge of the drawn tour while the solvers minimize the bottleneck
+pub fn draw_bottleneck_edge(
+    mut lines: ResMut<DebugLines>,
+    vertex_list: Res<VertexList>,
+    adjacency_matrix: Res<AdjacencyMatrix>,
+    shortest_cycle: Res<ShortestCycle>,
+    graph_settings: Res<GraphSettings>,
+) {
+    if graph_settings.objective != TourObjective::Bottleneck
+    {
+        return;
+    }
+
+    if let Some((vertex1, vertex2, _)) = bottleneck_edge(&shortest_cycle, &adjacency_matrix.matrix)
+    {
+        let (_, y1, x1) = vertex_list.vector[vertex1 as usize - 1];
+        let (_, y2, x2) = vertex_list.vector[vertex2 as usize - 1];
+
+        draw_thick_line(&mut lines, Vec3::new(x1, y1, 0.), Vec3::new(x2, y2, 0.), Color::RED, 4);
+    }
+}
+
+// Draws every edge coloured and thickened according to its pheromone level relative to the strongest edge, together with a legend
+fn draw_pheromone_overlay(
+    commands: &mut Commands,
+    lines: &mut DebugLines,
+    asset_server: &AssetServer,
+    win: &Window,
+    vertex_list: &VertexList,
+    pheromone_matrix: &DMatrix<f32>,
+) {
+    let vertex_count = pheromone_matrix.nrows().min(vertex_list.vector.len());
+    let maximum_pheromone = pheromone_matrix.max();
+
+    if maximum_pheromone > 0.0
+    {
+        for j in 0..vertex_count
+        {
+            for i in 0..j
+            {
+                let level = pheromone_matrix[(i, j)].max(pheromone_matrix[(j, i)]) / maximum_pheromone;
+
+                // Hardly visible trails would only clutter the canvas
+                if level < 0.01
+                {
+                    continue;
+                }
+
+                draw_thick_line(
+                    lines,
+                    Vec3::new(vertex_list.vector[i].2, vertex_list.vector[i].1, 0.),
+                    Vec3::new(vertex_list.vector[j].2, vertex_list.vector[j].1, 0.),
+                    heatmap_color(level),
+                    1 + (level * 4.0).round() as u32,
+                );
+            }
+        }
+    }
+
+    // Gradient bar in the bottom right corner
+    let legend_left = win.width() / 2. - 320.;
+    let legend_right = win.width() / 2. - 70.;
+    let legend_bottom = -win.height() / 2. + 60.;
+
+    for i in 0..10
+    {
+        lines.line_gradient(
+            Vec3::new(legend_left, legend_bottom + i as f32, 0.),
+            Vec3::new(legend_right, legend_bottom + i as f32, 0.),
+            0.0,
+            heatmap_color(0.0),
+            heatmap_color(1.0),
+        );
+    }
+
+    let legend_labels = [
+        (format!("Pheromone level (max {:.4})", maximum_pheromone), 75., 320.),
+        ("low".to_string(), 20., 320.),
+        ("high".to_string(), 20., 110.),
+    ];
+
+    for (label, bottom, right) in legend_labels
+    {
+        commands.spawn((
+            TextBundle::from_section(
+                label,
+                TextStyle {
+                    font: asset_server.load("fonts/FiraSans-ExtraLight.ttf"),
+                    font_size: 25.0,
+                    color: Color::WHITE,
+                },
+            )
+                .with_text_alignment(TextAlignment::Left)
+                .with_style(Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        bottom: Val::Px(bottom),
+                        left: Val::Px(win.width() - right),
+                        ..default()
+                    },
+                    ..default()
+                }),
+            PheromoneLegend,
+        ));
+    }
+}
+
+// Maps a normalized level from <0.0, 1.0> onto a blue - red - yellow colour scale
+fn heatmap_color(
+    level: f32,
+) -> Color {
+    let level = level.clamp(0.0, 1.0);
+
+    if level < 0.5
+    {
+        let t = level * 2.0;
+        Color::rgb(0.15 + 0.75 * t, 0.2, 0.8 - 0.6 * t)
+    } else {
+        let t = (level - 0.5) * 2.0;
+        Color::rgb(0.9 + 0.1 * t, 0.2 + 0.75 * t, 0.2 + 0.1 * t)
+    }
+}
+
+// Draws an arrow head in the middle of the edge pointing towards its end
+pub fn draw_arrow_head(
+    lines: &mut DebugLines,
+    start: Vec3,
+    end: Vec3,
+    color: Color,
+) {
+    let direction = (end - start).normalize_or_zero();
+    let normal = Vec3::new(-direction.y, direction.x, 0.);
+    let tip = (start + end) / 2. + direction * 6.;
+
+    lines.line_colored(tip, tip - direction * 12. + normal * 6., 0.0, color);
+    lines.line_colored(tip, tip - direction * 12. - normal * 6., 0.0, color);
+}
+
+// Debug lines are always one pixel wide, so wider lines are drawn as several parallel lines
+pub fn draw_thick_line(
+    lines: &mut DebugLines,
+    start: Vec3,
+    end: Vec3,
+    color: Color,
+    width: u32,
+) {
+    let normal = Vec3::new(start.y - end.y, end.x - start.x, 0.).normalize_or_zero();
+
+    for i in 0..width
+    {
+        let offset = normal * (i as f32 - (width - 1) as f32 / 2.);
+
+        lines.line_colored(start + offset, end + offset, 0.0, color);
+    }
+}
+
+// Pheromone update strategies, the ants themselves walk the graph the same way in every variant
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum AntSystemVariant {
+    // Every ant deposits pheromones proportional to the quality of its tour
+    Standard,
+    // Every ant deposits and the best-so-far tour gets an extra deposit weighted by elitist_weight
+    Elitist,
+    // Only the rank_size - 1 best ants of the iteration deposit, weighted by their rank, plus the best-so-far tour
+    RankBased,
+}
+
+// Decides on which vertex each ant starts its tour
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum AntStartPolicy {
+    // Every ant starts on vertex 1
+    Fixed,
+    // Every ant starts on a uniformly random vertex
+    Random,
+    // Ants are spread over the vertices one after another, continuing across iterations
+    RoundRobin,
+}
+
+// This resource stores the parameters used in the ant-colony-system equations for updating pheromones and probability calculation
+#[derive(Resource, Clone)]
+pub struct AntColonyParameters{
+    pub activate: bool,
+    pub variant: AntSystemVariant,
+    pub number_of_ants: u32,
+    pub pheromone_constant: f32,
+    pub pheromone_evaporation_rate: f32,
+    pub alpha: f32,
+    pub beta: f32,
+    pub elitist_weight: f32,
+    pub rank_size: u32,
+    pub candidate_list_size: u32,
+    pub ant_start: AntStartPolicy,
+    pub local_search: local_search::LocalSearch,
+    // Directed graphs keep a separate pheromone trail for each direction of an edge
+    pub directed: bool,
+    // Every ant derives its own random number generator from the seed, the iteration and its index
+    pub seed: u64,
+    pub iteration: u64,
+    // Stagnation handling, a restart resets the pheromones once either threshold is reached, 0 disables the threshold
+    pub restart_branching_factor: f32,
+    pub restart_iterations: u32,
+    pub last_improvement: u64,
+    pub restarts: u32,
+    pub pheromone_matrix: DMatrix<f32>,
+    // Heuristic information raised to the power of beta and the nearest neighbour lists, both only depend on the graph and beta
+    pub heuristic_matrix: DMatrix<f32>,
+    pub candidate_lists: Vec<Vec<u32>>,
+    // Ant tours stored as the order of visited vertices together with the tour length
+    pub ant_paths: Vec<(Vec<u32>, f32)>,
+    pub best_path: Option<(Vec<u32>, f32)>,
+}
+
+// This system draws the shortest path using the Ant-Colony Optimization algorithm
+pub fn ant_colony_optimization(
+    mut ant_colony_parameters: ResMut<AntColonyParameters>,
+    mut ant_animation: ResMut<AntAnimation>,
+    mut dynamic_graph: ResMut<dynamic::DynamicGraph>,
+    time: Res<Time>,
+    solver_graph: Res<SolverGraph>,
+    mut shortest_cycle: ResMut<ShortestCycle>,
+    mut ant_colony_stats_text: Query<&mut Text, With<app::AntColonyStatsText>>,
+){
+    if ant_colony_parameters.activate == true
+    {
+        let count = solver_graph.matrix.nrows() as u32;
+
+        // The graph was edited while the colony runs, the pheromones and the best tour are repaired instead of starting over
+        if dynamic_graph.changes.len() > 0 || solver_graph.is_changed()
+        {
+            if dynamic::solver_graph_current(&dynamic_graph, &solver_graph) == false
+            {
+                return;
+            }
+
+            dynamic::repair_colony(&mut ant_colony_parameters, &dynamic_graph.changes, &solver_graph);
+            dynamic_graph.changes = Vec::new();
+            ant_animation.tours = Vec::new();
+        }
+
+        // The solver graph changed size, for example when the open path mode was switched, so the colony starts over
+        if ant_colony_parameters.pheromone_matrix.nrows() != count as usize
+        {
+            reset_colony(&mut ant_colony_parameters, count);
+            ant_animation.tours = Vec::new();
+        }
+
+        ant_colony_parameters.directed = solver_graph.directed;
+
+        // The animated ants choose their vertices while they walk, so the pheromones are only updated once every ant closed its tour.
+        // Switching the animation off lets the ants of an unfinished iteration complete their tours at once
+        if ant_animation.activate == true || ant_animation.tours.len() > 0
+        {
+            // The console clears the cached heuristic information when beta or the candidate list size change, the ants then start the iteration over,
+            // changes of the graph are handled by repair_colony above, which clears the caches together with the tours
+            if ant_animation.tours.is_empty() || ant_colony_parameters.heuristic_matrix.nrows() != count as usize || ant_colony_parameters.candidate_lists.len() != count as usize
+            {
+                start_animated_iteration(&mut ant_colony_parameters, &mut ant_animation, &solver_graph);
+            }
+
+            ant_animation.progress = if ant_animation.activate { ant_animation.progress + ant_animation.speed * time.delta_seconds() } else { count as f32 };
+
+            // Each ant already knows the vertex it is walking to
+            let tour_length = (ant_animation.progress.floor() as usize + 2).min(count as usize);
+            step_animated_ants(&ant_colony_parameters, &mut ant_animation, &count, tour_length);
+
+            if ant_animation.progress < count as f32
+            {
+                return;
+            }
+
+            ant_colony_parameters.ant_paths = ant_animation.tours.drain(..)
+                .map(|tour| {
+                    let tour_length = local_search::tour_length(&tour, &solver_graph.matrix);
+                    (tour, tour_length)
+                })
+                .collect();
+            ant_colony_parameters.iteration += 1;
+        } else {
+            construct_colony_tours(&mut ant_colony_parameters, &solver_graph);
+        }
+
+        let stats = update_colony(&mut ant_colony_parameters, &solver_graph);
+        let best_path = ant_colony_parameters.best_path.clone().unwrap();
+
+        store_shortest_cycle(&best_path.0, best_path.1, &solver_graph, &mut shortest_cycle);
+
+        for mut text in &mut ant_colony_stats_text
+        {
+            text.sections[0].value = stats.clone() + &route_summary(&shortest_cycle) + &objective_summary(&shortest_cycle, &solver_graph);
+        }
+    }
+}
+
+// This resource stores the state of the ant animation, which lets the ants of the single colony walk their tours on screen
+#[derive(Resource)]
+pub struct AntAnimation {
+    pub activate: bool,
+    // Number of edges every ant walks per second
+    pub speed: f32,
+    // Number of the ant whose transition probabilities are shown, 0 follows no ant
+    pub follow_ant: u32,
+    // Number of edges the ants already walked in the current iteration
+    pub progress: f32,
+    // Partial tours of the ants, every ant chooses its next vertex when it arrives on the previous one
+    pub tours: Vec<Vec<u32>>,
+    pub rngs: Vec<StdRng>,
+    pub choice_info: DMatrix<f32>,
+}
+
+#[derive(Component)]
+struct AntAgent;
+
+#[derive(Component)]
+struct TransitionProbabilityLabel;
+
+// This system draws the ants of the running animation, the followed ant also shows its path and the probabilities of its next step
+pub fn draw_ants(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut lines: ResMut<DebugLines>,
+    asset_server: Res<AssetServer>,
+    window: Query<&mut Window>,
+    vertex_list: Res<VertexList>,
+    solver_graph: Res<SolverGraph>,
+    ant_colony_parameters: Res<AntColonyParameters>,
+    ant_animation: Res<AntAnimation>,
+    mut ant_handles: Local<Option<(Handle<Mesh>, Handle<ColorMaterial>, Handle<ColorMaterial>)>>,
+) {
+    commands.despawn_all::<With<AntAgent>>();
+    commands.despawn_all::<With<TransitionProbabilityLabel>>();
+
+    if ant_animation.activate == false || ant_animation.tours.is_empty()
+    {
+        return;
+    }
+
+    let win = window.single();
+
+    // The same mesh and materials are shared by all ants in every frame
+    let (ant_mesh, ant_material, followed_ant_material) = ant_handles.get_or_insert_with(|| (
+        meshes.add(shape::Circle::new(6.).into()),
+        materials.add(ColorMaterial::from(Color::ORANGE)),
+        materials.add(ColorMaterial::from(Color::CYAN)),
+    )).clone();
+
+    let position = |vertex: u32| Vec3::new(vertex_list.vector[vertex as usize - 1].2, vertex_list.vector[vertex as usize - 1].1, 0.);
+
+    // Copies of the depot are drawn on the depot, the dummy vertex of an open path has no position, so the ant waits on the previous vertex instead
+    let position_in_tour = |tour: &Vec<u32>, index: usize| {
+        let vertex = tour[index % tour.len()];
+
+        if vertex <= solver_graph.vertex_count
+        {
+            position(vertex)
+        } else if solver_graph.salesmen > 1 {
+            position(solver_graph.depot)
+        } else {
+            position(tour[(index + tour.len() - 1) % tour.len()])
+        }
+    };
+
+    let step = ant_animation.progress.floor() as usize;
+    let step_progress = ant_animation.progress.fract();
+
+    for (ant, tour) in ant_animation.tours.iter().enumerate()
+    {
+        let from = position_in_tour(tour, step);
+        let to = position_in_tour(tour, step + 1);
+        let is_followed = ant as u32 + 1 == ant_animation.follow_ant;
+
+        commands
+            .spawn(MaterialMesh2dBundle {
+                mesh: ant_mesh.clone().into(),
+                material: if is_followed { followed_ant_material.clone() } else { ant_material.clone() },
+                transform: Transform::from_translation(from.lerp(to, step_progress) + Vec3::new(0., 0., if is_followed { 2. } else { 1. }))
+                    .with_scale(Vec3::splat(if is_followed { 1.6 } else { 1. })),
+                ..default()
+            })
+            .insert(AntAgent);
+
+        if is_followed == false
+        {
+            continue;
+        }
+
+        for i in 0..step.min(tour.len() - 1)
+        {
+            draw_thick_line(&mut lines, position_in_tour(tour, i), position_in_tour(tour, i + 1), Color::CYAN, 3);
+        }
+
+        // The closing edge back to the start vertex is not a choice, so probabilities are only shown while unvisited vertices remain
+        if step + 1 < tour.len()
+        {
+            let probabilities = transition_probabilities(
+                &tour[0..=step],
+                &ant_animation.choice_info,
+                &ant_colony_parameters.heuristic_matrix,
+                &ant_colony_parameters.candidate_lists,
+                &(ant_animation.choice_info.nrows() as u32),
+            );
+
+            let maximum_probability = probabilities.iter().map(|probability| probability.1).fold(0.0, f32::max);
+
+            for (vertex, probability) in probabilities.into_iter().filter(|(vertex, _)| *vertex <= solver_graph.vertex_count)
+            {
+                lines.line_colored(from, position(vertex), 0.0, heatmap_color(probability / maximum_probability));
+
+                commands.spawn((
+                    TextBundle::from_section(
+                        format!("{:.1}%", probability * 100.0),
+                        TextStyle {
+                            font: asset_server.load("fonts/FiraSans-ExtraLight.ttf"),
+                            font_size: 20.0,
+                            color: if vertex == tour[step + 1] { Color::CYAN } else { Color::WHITE },
+                        },
+                    )
+                        .with_text_alignment(TextAlignment::Left)
+                        .with_style(Style {
+                            position_type: PositionType::Absolute,
+                            position: UiRect {
+                                bottom: Val::Px(position(vertex).y + (win.height() / 2.) + 15.),
+                                left: Val::Px(position(vertex).x + (win.width() / 2.) + 15.),
+                                ..default()
+                            },
+                            ..default()
+                        }),
+                    TransitionProbabilityLabel,
+                ));
+            }
+        }
+    }
+}
+
+// Clears everything the colony learned about the previous graph so that it can start solving from scratch
+pub fn reset_colony(
+    ant_colony_parameters: &mut AntColonyParameters,
+    vertex_count: u32,
+) {
+    ant_colony_parameters.pheromone_matrix = DMatrix::from_diagonal_element(vertex_count as usize, vertex_count as usize, 0.0);
+    ant_colony_parameters.heuristic_matrix = DMatrix::from_diagonal_element(0, 0, 0.0);
+    ant_colony_parameters.candidate_lists = Vec::new();
+    ant_colony_parameters.ant_paths = Vec::new();
+    ant_colony_parameters.best_path = None;
+    ant_colony_parameters.iteration = 0;
+    ant_colony_parameters.last_improvement = 0;
+    ant_colony_parameters.restarts = 0;
+}
+
+// Information the colonies of the multi-colony mode exchange every migration_interval iterations
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ColonyMigration {
+    // The colonies form a ring, every colony lays pheromones on the best tour of its neighbour and adopts it when it is shorter than its own
+    BestTour,
+    // Every pheromone matrix is blended with the average pheromone matrix of all colonies
+    PheromoneBlend,
+}
+
+// This resource stores the independent colonies of the multi-colony mode, every colony has its own pheromone matrix
+#[derive(Resource)]
+pub struct MultiColonyParameters {
+    pub activate: bool,
+    pub number_of_colonies: u32,
+    // Colony i takes alpha and beta from position i of these lists (wrapping around), empty lists keep the values of the single colony
+    pub colony_alphas: Vec<f32>,
+    pub colony_betas: Vec<f32>,
+    pub migration: ColonyMigration,
+    pub migration_interval: u32,
+    pub blend_rate: f32,
+    pub migrations: u32,
+    pub colonies: Vec<AntColonyParameters>,
+}
+
+// This system advances every colony of the multi-colony mode by one iteration and draws the best tour found by any of them
+pub fn multi_colony_optimization(
+    mut multi_colony_parameters: ResMut<MultiColonyParameters>,
+    mut dynamic_graph: ResMut<dynamic::DynamicGraph>,
+    solver_graph: Res<SolverGraph>,
+    mut shortest_cycle: ResMut<ShortestCycle>,
+    mut ant_colony_stats_text: Query<&mut Text, With<app::AntColonyStatsText>>,
+){
+    if multi_colony_parameters.activate == true
+    {
+        let count = solver_graph.matrix.nrows() as u32;
+
+        if dynamic_graph.changes.len() > 0 || solver_graph.is_changed()
+        {
+            if dynamic::solver_graph_current(&dynamic_graph, &solver_graph) == false
+            {
+                return;
+            }
+
+            for colony in multi_colony_parameters.colonies.iter_mut()
+            {
+                dynamic::repair_colony(colony, &dynamic_graph.changes, &solver_graph);
+            }
+
+            dynamic_graph.changes = Vec::new();
+        }
+
+        for colony in multi_colony_parameters.colonies.iter_mut()
+        {
+            if colony.pheromone_matrix.nrows() != count as usize
+            {
+                reset_colony(colony, count);
+            }
+
+            colony.directed = solver_graph.directed;
+            colony_iteration(colony, &solver_graph);
+        }
+
+        let iteration = multi_colony_parameters.colonies[0].iteration;
+        let migration_interval = multi_colony_parameters.migration_interval as u64;
+
+        if migration_interval > 0 && iteration % migration_interval == 0
+        {
+            let migration = multi_colony_parameters.migration;
+            let blend_rate = multi_colony_parameters.blend_rate;
+
+            migrate_colonies(&mut multi_colony_parameters.colonies, migration, blend_rate);
+            multi_colony_parameters.migrations += 1;
+        }
+
+        let mut stats = format!("Iteration: {}, migrations: {}", iteration, multi_colony_parameters.migrations);
+        let mut best_path: Option<(Vec<u32>, f32)> = None;
+
+        for (index, colony) in multi_colony_parameters.colonies.iter().enumerate()
+        {
+            let colony_best_path = colony.best_path.clone().unwrap();
+
+            stats += &format!(
+                "\nColony {} (alpha {}, beta {}): best tour {:.1}, iterations since last improvement: {}",
+                index + 1,
+                colony.alpha,
+                colony.beta,
+                objective_value(&colony_best_path.0, colony_best_path.1, &solver_graph),
+                colony.iteration - colony.last_improvement,
+            );
+
+            let is_better = match &best_path {
+                Some(best_path) => colony_best_path.1 < best_path.1,
+                None => true,
+            };
+
+            if is_better
+            {
+                best_path = Some(colony_best_path);
+            }
+        }
+
+        let best_path = best_path.unwrap();
+
+        store_shortest_cycle(&best_path.0, best_path.1, &solver_graph, &mut shortest_cycle);
+
+        for mut text in &mut ant_colony_stats_text
+        {
+            text.sections[0].value = stats.clone() + &route_summary(&shortest_cycle) + &objective_summary(&shortest_cycle, &solver_graph);
+        }
+    }
+}
+
+// Exchanges information between the colonies of the multi-colony mode
+fn migrate_colonies(
+    colonies: &mut Vec<AntColonyParameters>,
+    migration: ColonyMigration,
+    blend_rate: f32,
+) {
+    let number_of_colonies = colonies.len();
+
+    match migration {
+        ColonyMigration::BestTour => {
+            let best_paths: Vec<Option<(Vec<u32>, f32)>> = colonies.iter().map(|colony| colony.best_path.clone()).collect();
+
+            for i in 0..number_of_colonies
+            {
+                if let Some(received_path) = &best_paths[(i + number_of_colonies - 1) % number_of_colonies]
+                {
+                    let colony = &mut colonies[i];
+                    let amount = colony.pheromone_constant / received_path.1;
+
+                    deposit_pheromones(&mut colony.pheromone_matrix, &received_path.0, amount, colony.directed);
+
+                    let is_better = match &colony.best_path {
+                        Some(best_path) => received_path.1 < best_path.1,
+                        None => true,
+                    };
+
+                    if is_better
+                    {
+                        colony.best_path = Some(received_path.clone());
+                        colony.last_improvement = colony.iteration;
+                    }
+                }
+            }
+        }
+        ColonyMigration::PheromoneBlend => {
+            let mut average_pheromone_matrix = colonies[0].pheromone_matrix.clone() * 0.0;
+
+            for colony in colonies.iter()
+            {
+                average_pheromone_matrix += &colony.pheromone_matrix;
+            }
+
+            average_pheromone_matrix /= number_of_colonies as f32;
+
+            for colony in colonies.iter_mut()
+            {
+                colony.pheromone_matrix = &colony.pheromone_matrix * (1.0 - blend_rate) + &average_pheromone_matrix * blend_rate;
+            }
+        }
+    }
+}
+
+// Runs a single iteration of the colony: releases the ants, improves their tours, updates the pheromones and handles stagnation,
+// returns the statistics of the iteration that are shown on screen
+fn colony_iteration(
+    ant_colony_parameters: &mut AntColonyParameters,
+    solver_graph: &SolverGraph,
+) -> String {
+    construct_colony_tours(ant_colony_parameters, solver_graph);
+
+    return update_colony(ant_colony_parameters, solver_graph);
+}
+
+// First half of an iteration: releases the ants, their tours are stored in ant_paths in the order the ants walked them
+fn construct_colony_tours(
+    ant_colony_parameters: &mut AntColonyParameters,
+    solver_graph: &SolverGraph,
+) {
+    let count = solver_graph.matrix.nrows() as u32;
+    let choice_info = prepare_colony_iteration(ant_colony_parameters, solver_graph);
+
+    ant_colony_parameters.ant_paths = release_ants(
+        &solver_graph.matrix,
+        &choice_info,
+        &ant_colony_parameters.heuristic_matrix,
+        &ant_colony_parameters.candidate_lists,
+        &count,
+        &ant_colony_parameters.number_of_ants,
+        ant_colony_parameters.ant_start,
+        ant_colony_parameters.seed,
+        ant_colony_parameters.iteration,
+    );
+
+    ant_colony_parameters.iteration += 1;
+}
+
+// Refreshes the cached heuristic information and candidate lists if needed and returns the choice information of the coming iteration
+fn prepare_colony_iteration(
+    ant_colony_parameters: &mut AntColonyParameters,
+    solver_graph: &SolverGraph,
+) -> DMatrix<f32> {
+    let adjacency_matrix = &solver_graph.matrix;
+    let count = adjacency_matrix.nrows() as u32;
+
+    // Both caches are cleared by the console when beta or the candidate list size change and by dynamic::repair_colony or reset_colony
+    // when the solver graph changes
+    if ant_colony_parameters.heuristic_matrix.nrows() != count as usize
+    {
+        ant_colony_parameters.heuristic_matrix = heuristic_matrix(adjacency_matrix, ant_colony_parameters.beta);
+    }
+
+    if ant_colony_parameters.candidate_lists.len() != count as usize
+    {
+        ant_colony_parameters.candidate_lists = candidate_lists(adjacency_matrix, ant_colony_parameters.candidate_list_size);
+    }
+
+    // The choice information only changes with the pheromones, so it is computed once per iteration instead of once per ant step
+    choice_info_matrix(
+        &ant_colony_parameters.pheromone_matrix,
+        &ant_colony_parameters.heuristic_matrix,
+        ant_colony_parameters.alpha,
+    )
+}
+
+// Releases the ants of the animation, they only stand on their start vertex until step_animated_ants moves them
+fn start_animated_iteration(
+    ant_colony_parameters: &mut AntColonyParameters,
+    ant_animation: &mut AntAnimation,
+    solver_graph: &SolverGraph,
+) {
+    let count = solver_graph.matrix.nrows() as u32;
+
+    ant_animation.choice_info = prepare_colony_iteration(ant_colony_parameters, solver_graph);
+    ant_animation.rngs = Vec::with_capacity(ant_colony_parameters.number_of_ants as usize);
+    ant_animation.tours = Vec::with_capacity(ant_colony_parameters.number_of_ants as usize);
+    ant_animation.progress = 0.0;
+
+    // Every ant uses the same random numbers as in release_ants, so animating the colony does not change its result
+    for ant in 0..ant_colony_parameters.number_of_ants
+    {
+        let mut rng = StdRng::seed_from_u64(ant_seed(ant_colony_parameters.seed, ant_colony_parameters.iteration, ant));
+        let start_vertex = ant_start_vertex(
+            &mut rng,
+            ant,
+            ant_colony_parameters.ant_start,
+            &count,
+            &ant_colony_parameters.number_of_ants,
+            ant_colony_parameters.iteration,
+        );
+
+        ant_animation.rngs.push(rng);
+        ant_animation.tours.push(vec![start_vertex]);
+    }
+}
+
+// Lets every ant of the animation choose its next vertices until its tour has the given length
+fn step_animated_ants(
+    ant_colony_parameters: &AntColonyParameters,
+    ant_animation: &mut AntAnimation,
+    vertex_count: &u32,
+    tour_length: usize,
+) {
+    let mut candidates: Vec<u32> = Vec::new();
+    let AntAnimation { rngs, tours, choice_info, .. } = ant_animation;
+
+    for (rng, tour) in rngs.iter_mut().zip(tours.iter_mut())
+    {
+        if tour.len() >= tour_length
+        {
+            continue;
+        }
+
+        let mut visited_vertices = VisitedVertices::new(*vertex_count);
+
+        for vertex in tour.iter()
+        {
+            visited_vertices.insert(*vertex);
+        }
+
+        while tour.len() < tour_length
+        {
+            let next_vertex = choose_next_vertex(
+                rng,
+                tour[tour.len() - 1],
+                &visited_vertices,
+                choice_info,
+                &ant_colony_parameters.heuristic_matrix,
+                &ant_colony_parameters.candidate_lists,
+                vertex_count,
+                &mut candidates,
+            );
+
+            visited_vertices.insert(next_vertex);
+            tour.push(next_vertex);
+        }
+    }
+}
+
+// Second half of an iteration: improves the ant tours, updates the pheromones and handles stagnation, returns the statistics of the iteration
+fn update_colony(
+    ant_colony_parameters: &mut AntColonyParameters,
+    solver_graph: &SolverGraph,
+) -> String {
+    let adjacency_matrix = &solver_graph.matrix;
+    let count = adjacency_matrix.nrows() as u32;
+
+    for ant_path in ant_colony_parameters.ant_paths.iter_mut()
+    {
+        normalize_tour(&mut ant_path.0);
+    }
+
+    let mut ant_paths = ant_colony_parameters.ant_paths.clone();
+    let mut stats = format!("Iteration: {}", ant_colony_parameters.iteration);
+
+    if ant_colony_parameters.local_search != local_search::LocalSearch::None
+    {
+        let average_before = average_tour_cost(&ant_paths, solver_graph);
+
+        improve_ant_paths(
+            &mut ant_paths,
+            ant_colony_parameters.local_search,
+            solver_graph,
+            &ant_colony_parameters.candidate_lists,
+            ant_colony_parameters.directed,
+        );
+
+        let average_after = average_tour_cost(&ant_paths, solver_graph);
+
+        stats += &format!(
+            ", average ant: {:.1} -> {:.1} after local search ({:.2}% better)",
+            average_before,
+            average_after,
+            100.0 * (average_before - average_after) / average_before,
+        );
+
+        ant_colony_parameters.ant_paths = ant_paths.clone();
+    }
+
+    // The ants build their tours by the total length, other objectives only change how the finished tours are rated
+    for ant_path in ant_paths.iter_mut()
+    {
+        ant_path.1 = tour_cost(&ant_path.0, ant_path.1, solver_graph);
+    }
+
+    for ant_path in &ant_paths
+    {
+        let is_better = match &ant_colony_parameters.best_path {
+            Some(best_path) => ant_path.1 < best_path.1,
+            None => true,
+        };
+
+        if is_better
+        {
+            ant_colony_parameters.best_path = Some(ant_path.clone());
+            ant_colony_parameters.last_improvement = ant_colony_parameters.iteration;
+        }
+    }
+
+    let best_path = ant_colony_parameters.best_path.clone().unwrap();
+    let pheromone_matrix = ant_colony_parameters.pheromone_matrix.clone();
+
+    ant_colony_parameters.pheromone_matrix = match ant_colony_parameters.variant {
+        AntSystemVariant::Standard => update_pheromones(
+            pheromone_matrix,
+            &ant_paths,
+            &ant_colony_parameters.pheromone_constant,
+            &ant_colony_parameters.pheromone_evaporation_rate,
+            ant_colony_parameters.directed,
+        ),
+        AntSystemVariant::Elitist => update_pheromones_elitist(
+            pheromone_matrix,
+            &ant_paths,
+            &best_path,
+            &ant_colony_parameters.pheromone_constant,
+            &ant_colony_parameters.pheromone_evaporation_rate,
+            &ant_colony_parameters.elitist_weight,
+            ant_colony_parameters.directed,
+        ),
+        AntSystemVariant::RankBased => update_pheromones_rank_based(
+            pheromone_matrix,
+            &ant_paths,
+            &best_path,
+            &ant_colony_parameters.pheromone_constant,
+            &ant_colony_parameters.pheromone_evaporation_rate,
+            &ant_colony_parameters.rank_size,
+            ant_colony_parameters.directed,
+        ),
+    };
+
+    if best_path.1.is_infinite()
+    {
+        stats += "\nNo tour avoiding the forbidden edges found yet";
+    }
+
+    let branching_factor = lambda_branching_factor(&ant_colony_parameters.pheromone_matrix, 0.05);
+    let ant_distance = average_ant_distance(&ant_paths, ant_colony_parameters.directed);
+    let iterations_since_improvement = ant_colony_parameters.iteration - ant_colony_parameters.last_improvement;
+
+    stats += &format!(
+        "\nBranching factor: {:.3}, average distance between ants: {:.1} edges, iterations since last improvement: {}, restarts: {}",
+        branching_factor,
+        ant_distance,
+        iterations_since_improvement,
+        ant_colony_parameters.restarts,
+    );
+
+    // Once the colony stagnates the pheromones are reset, the best-so-far tour is kept
+    if (ant_colony_parameters.restart_branching_factor > 0.0 && branching_factor <= ant_colony_parameters.restart_branching_factor)
+        || (ant_colony_parameters.restart_iterations > 0 && iterations_since_improvement >= ant_colony_parameters.restart_iterations as u64)
+    {
+        ant_colony_parameters.pheromone_matrix = DMatrix::from_diagonal_element(count as usize, count as usize, 0.0);
+        ant_colony_parameters.last_improvement = ant_colony_parameters.iteration;
+        ant_colony_parameters.restarts += 1;
+
+        println!("colony restarted after stagnation, best tour so far: {}", best_path.1);
+    }
+
+    return stats;
+}
+
+// Precomputes the heuristic information (1 / distance) raised to the power of beta for every edge
+fn heuristic_matrix(
+    adjacency_matrix: &DMatrix<f32>,
+    beta: f32,
+) -> DMatrix<f32> {
+    let vertex_count = adjacency_matrix.nrows();
+    let mut heuristic_matrix = DMatrix::from_diagonal_element(vertex_count, vertex_count, 0.0);
+
+    for j in 0..vertex_count
+    {
+        for i in 0..vertex_count
+        {
+            if i != j
+            {
+                // Vertices placed on top of each other would divide by zero
+                heuristic_matrix[(i, j)] = f32::powf(1.0 / adjacency_matrix[(i, j)].max(f32::EPSILON), beta).min(f32::MAX);
+            }
+        }
+    }
+
+    return heuristic_matrix;
+}
+
+// Stores the candidate_list_size nearest neighbours of every vertex sorted by distance, size 0 means every other vertex is a candidate
+fn candidate_lists(
+    adjacency_matrix: &DMatrix<f32>,
+    candidate_list_size: u32,
+) -> Vec<Vec<u32>> {
+    let vertex_count = adjacency_matrix.nrows();
+    let mut candidate_lists = Vec::with_capacity(vertex_count);
+
+    let size = if candidate_list_size == 0 {
+        vertex_count.saturating_sub(1)
+    } else {
+        (candidate_list_size as usize).min(vertex_count.saturating_sub(1))
+    };
+
+    for j in 0..vertex_count
+    {
+        let mut neighbours: Vec<u32> = (0..vertex_count as u32).filter(|i| *i as usize != j).collect();
+
+        if size < neighbours.len()
+        {
+            neighbours.select_nth_unstable_by(size, |a, b| adjacency_matrix[(j, *a as usize)].total_cmp(&adjacency_matrix[(j, *b as usize)]));
+            neighbours.truncate(size);
+        }
+
+        neighbours.sort_by(|a, b| adjacency_matrix[(j, *a as usize)].total_cmp(&adjacency_matrix[(j, *b as usize)]));
+
+        // Candidate lists store vertex numbers, same as the tours
+        candidate_lists.push(neighbours.iter().map(|i| i + 1).collect());
+    }
+
+    return candidate_lists;
+}
+
+// Combines the pheromone matrix with the precomputed heuristic matrix into pheromone^alpha * heuristic^beta
+fn choice_info_matrix(
+    pheromone_matrix: &DMatrix<f32>,
+    heuristic_matrix: &DMatrix<f32>,
+    alpha: f32,
+) -> DMatrix<f32> {
+    pheromone_matrix.zip_map(heuristic_matrix, |pheromone, heuristic| f32::powf(pheromone, alpha) * heuristic)
+}
+
+// Fixed size set of visited vertices, one bit per vertex
+struct VisitedVertices {
+    bits: Vec<u64>,
+}
+
+impl VisitedVertices {
+    fn new(vertex_count: u32) -> VisitedVertices {
+        VisitedVertices {
+            bits: vec![0; (vertex_count as usize + 63) / 64],
+        }
+    }
+
+    fn insert(&mut self, vertex: u32) {
+        let index = vertex as usize - 1;
+        self.bits[index / 64] |= 1 << (index % 64);
+    }
+
+    fn contains(&self, vertex: u32) -> bool {
+        let index = vertex as usize - 1;
+        self.bits[index / 64] & (1 << (index % 64)) != 0
+    }
+}
+
+// Derives the seed of a single ant so that its tour does not depend on which thread constructed it (splitmix64 finalizer)
+fn ant_seed(
+    seed: u64,
+    iteration: u64,
+    ant: u32,
+) -> u64 {
+    let mut z = seed
+        .wrapping_add(iteration.wrapping_mul(0x9E3779B97F4A7C15))
+        .wrapping_add((ant as u64 + 1).wrapping_mul(0xD1B54A32D192ED03));
+
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+
+    return z ^ (z >> 31);
+}
+
+// This function simulates ants walking through each vertex until a hamiltonian cycle is complete, the ants are split among the compute threads
+fn release_ants(
+    adjacency_matrix: &DMatrix<f32>,
+    choice_info: &DMatrix<f32>,
+    heuristic_matrix: &DMatrix<f32>,
+    candidate_lists: &Vec<Vec<u32>>,
+    vertex_count: &u32,
+    number_of_ants: &u32,
+    ant_start: AntStartPolicy,
+    seed: u64,
+    iteration: u64,
+) -> Vec<(Vec<u32>, f32)> {
+    let task_pool = ComputeTaskPool::get();
+    let chunk_size = ((*number_of_ants as usize + task_pool.thread_num() - 1) / task_pool.thread_num()).max(1) as u32;
+
+    // The scope returns the results in the order the tasks were spawned, so the ants keep their order as well
+    let chunks = task_pool.scope(|scope| {
+        let mut first_ant = 0;
+
+        while first_ant < *number_of_ants
+        {
+            let last_ant = (first_ant + chunk_size).min(*number_of_ants);
 
-                    distance_x = (x2 - x1).abs();
-                    distance_y = (y2 - y1).abs();
+            scope.spawn(async move {
+                let mut ant_paths = Vec::with_capacity((last_ant - first_ant) as usize);
 
-                    edge_list.vector.push((
-                        count,
-                        i,
-                        (distance_x.powf(2.) + distance_y.powf(2.)).sqrt(),
+                for ant in first_ant..last_ant
+                {
+                    let mut rng = StdRng::seed_from_u64(ant_seed(seed, iteration, ant));
+
+                    let start_vertex = ant_start_vertex(&mut rng, ant, ant_start, vertex_count, number_of_ants, iteration);
+
+                    ant_paths.push(construct_ant_tour(
+                        &mut rng,
+                        start_vertex,
+                        adjacency_matrix,
+                        choice_info,
+                        heuristic_matrix,
+                        candidate_lists,
+                        vertex_count,
                     ));
-                    println!(
-                        "new edge: {:?}",
-                        edge_list.vector[(edge_list.vector.len() - 1) as usize]
-                    );
                 }
-            }
-
-            adjacency_matrix.matrix = DMatrix::from_diagonal_element(count as usize, count as usize, 0.0);
-
-            for i in 0..edge_list.count
-            {
-                adjacency_matrix.matrix[(edge_list.vector[i as usize].1 as usize - 1, edge_list.vector[i as usize].0 as usize - 1)] = edge_list.vector[i as usize].2;
-                adjacency_matrix.matrix[(edge_list.vector[i as usize].0 as usize - 1 ,edge_list.vector[i as usize].1 as usize - 1)] = edge_list.vector[i as usize].2;
-            }
 
-            for i in 1..count{
-                possible_cycles_count *= (count - i) as u128;
-            }
+                ant_paths
+            });
 
-            possible_cycles_count /= 2;
+            first_ant = last_ant;
+        }
+    });
 
-            for mut edge_count_text in &mut info_text_param_set.p1().iter_mut() {
-                edge_count_text.sections[0].value =
-                    format!("Number of edges: {}", edge_list.count.to_string())
-            }
+    return chunks.into_iter().flatten().collect();
+}
 
-            for mut possible_cycles_text in &mut info_text_param_set.p2().iter_mut()
-            {
-                possible_cycles_text.sections[0].value = format!("Number of possible cycles: {}", possible_cycles_count);
-            }
-        }
+// Vertex on which the ant is released in the given iteration
+fn ant_start_vertex<R: Rng>(
+    rng: &mut R,
+    ant: u32,
+    ant_start: AntStartPolicy,
+    vertex_count: &u32,
+    number_of_ants: &u32,
+    iteration: u64,
+) -> u32 {
+    match ant_start {
+        AntStartPolicy::Fixed => 1,
+        AntStartPolicy::Random => rng.gen_range(1..=*vertex_count),
+        AntStartPolicy::RoundRobin => ((iteration * *number_of_ants as u64 + ant as u64) % *vertex_count as u64) as u32 + 1,
     }
 }
 
-#[derive(Component)]
-struct Vertex;
+// Walks a single ant from the start vertex through the whole graph and returns its tour together with the tour length
+fn construct_ant_tour<R: Rng>(
+    rng: &mut R,
+    start_vertex: u32,
+    adjacency_matrix: &DMatrix<f32>,
+    choice_info: &DMatrix<f32>,
+    heuristic_matrix: &DMatrix<f32>,
+    candidate_lists: &Vec<Vec<u32>>,
+    vertex_count: &u32,
+) -> (Vec<u32>, f32) {
+    let mut visited_vertices = VisitedVertices::new(*vertex_count);
+    let mut tour: Vec<u32> = Vec::with_capacity(*vertex_count as usize);
+    let mut candidates: Vec<u32> = Vec::new();
+    let mut ant_tour_length: f32 = 0.0;
 
-#[derive(Component)]
-struct VertexNumber;
+    let mut current_vertex: u32 = start_vertex;
+    let mut previous_vertex: u32;
 
-#[derive(Component)]
-struct Edge;
+    tour.push(current_vertex);
+    visited_vertices.insert(current_vertex);
 
-// This function draws the graph on the canvas on every new frame
-pub fn draw_graph(
-    mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-    mut lines: ResMut<DebugLines>,
-    asset_server: Res<AssetServer>,
-    window: Query<&mut Window>,
-    vertex_list: ResMut<VertexList>,
-    edge_list: ResMut<EdgeList>,
-    shortest_cycle: ResMut<ShortestCycle>,
-) {
-    let mut x1: f32;
-    let mut y1: f32;
+    while tour.len() < *vertex_count as usize
+    {
+        previous_vertex = current_vertex;
+
+        current_vertex = choose_next_vertex(
+            rng,
+            current_vertex,
+            &visited_vertices,
+            choice_info,
+            heuristic_matrix,
+            candidate_lists,
+            vertex_count,
+            &mut candidates,
+        );
 
-    let mut x2: f32;
-    let mut y2: f32;
+        visited_vertices.insert(current_vertex);
+        tour.push(current_vertex);
 
-    let win = window.single();
+        ant_tour_length += adjacency_matrix[(previous_vertex as usize - 1, current_vertex as usize - 1)];
+    }
 
-    commands.despawn_all::<With<Vertex>>();
-    commands.despawn_all::<With<VertexNumber>>();
+    ant_tour_length += adjacency_matrix[(current_vertex as usize - 1, start_vertex as usize - 1)];
 
-    for i in 0..edge_list.count
-    {
-        x1 = vertex_list.vector[(edge_list.vector[(i as usize)].0 - 1) as usize].2;
-        y1 = vertex_list.vector[(edge_list.vector[(i as usize)].0 - 1) as usize].1;
+    return (tour, ant_tour_length);
+}
 
-        x2 = vertex_list.vector[(edge_list.vector[(i as usize)].1 - 1) as usize].2;
-        y2 = vertex_list.vector[(edge_list.vector[(i as usize)].1 - 1) as usize].1;
+// Chooses the next vertex of an ant standing on current_vertex, the candidates vector is only passed in to be reused between the steps
+fn choose_next_vertex<R: Rng>(
+    rng: &mut R,
+    current_vertex: u32,
+    visited_vertices: &VisitedVertices,
+    choice_info: &DMatrix<f32>,
+    heuristic_matrix: &DMatrix<f32>,
+    candidate_lists: &Vec<Vec<u32>>,
+    vertex_count: &u32,
+    candidates: &mut Vec<u32>,
+) -> u32 {
+    let column = current_vertex as usize - 1;
+    let mut probability_sum: f32 = 0.0;
 
-        lines.line_colored(
-            Vec3::new(x1, y1, 0.),
-            Vec3::new(x2, y2, 0.),
-            0.0,
-            Color::BLACK,
-        );
-    }
+    candidates.clear();
 
-    for i in 0..shortest_cycle.vector.len()
+    for candidate in &candidate_lists[column]
     {
-        x1 = vertex_list.vector[(shortest_cycle.vector[(i as usize)].0 - 1) as usize].2;
-        y1 = vertex_list.vector[(shortest_cycle.vector[(i as usize)].0 - 1) as usize].1;
+        // Forbidden edges have an infinite weight and therefore no heuristic information
+        if !visited_vertices.contains(*candidate) && heuristic_matrix[(column, *candidate as usize - 1)] > 0.0
+        {
+            candidates.push(*candidate);
+            probability_sum += choice_info[(column, *candidate as usize - 1)];
+        }
+    }
 
-        x2 = vertex_list.vector[(shortest_cycle.vector[(i as usize)].1 - 1) as usize].2;
-        y2 = vertex_list.vector[(shortest_cycle.vector[(i as usize)].1 - 1) as usize].1;
+    if candidates.is_empty()
+    {
+        fallback_vertex(visited_vertices, column, choice_info, heuristic_matrix, vertex_count)
+    } else if probability_sum > 0.0 {
+        // Roulette wheel selection over the unvisited candidates
+        let mut target = rng.gen::<f32>() * probability_sum;
+        let mut chosen_vertex = candidates[candidates.len() - 1];
 
-        lines.line_colored(
-            Vec3::new(x1, y1, 0.),
-            Vec3::new(x2, y2, 0.),
-            0.0,
-            Color::WHITE,
-        );
-    }
+        for candidate in candidates.iter()
+        {
+            target -= choice_info[(column, *candidate as usize - 1)];
 
-    for i in 0..vertex_list.count {
-        commands
-            .spawn(MaterialMesh2dBundle {
-                mesh: meshes.add(shape::Circle::new(15.).into()).into(),
-                material: materials.add(ColorMaterial::from(Color::BLACK)),
-                transform: Transform::from_translation(Vec3::new(
-                    vertex_list.vector[(i as usize)].2,
-                    vertex_list.vector[(i as usize)].1,
-                    0.,
-                )),
-                ..default()
-            })
-            .insert(Vertex);
+            if target <= 0.0
+            {
+                chosen_vertex = *candidate;
+                break;
+            }
+        }
 
-        commands.spawn((
-            // Create a TextBundle that has a Text with a single section.
-            TextBundle::from_section(
-                // Accepts a `String` or any type that converts into a `String`, such as `&str`
-                vertex_list.vector[i as usize].0.to_string(),
-                TextStyle {
-                    font: asset_server.load("fonts/FiraSans-ExtraLight.ttf"),
-                    font_size: 30.0,
-                    color: Color::WHITE,
-                },
-            ) // Set the alignment of the Text
-                .with_text_alignment(TextAlignment::Left)
-                // Set the style of the TextBundle itself.
-                .with_style(Style {
-                    position_type: PositionType::Absolute,
-                    position: UiRect {
-                        bottom: Val::Px(vertex_list.vector[(i as usize)].1 + (win.height() / 2.) - 50.),
-                        left: Val::Px(vertex_list.vector[(i as usize)].2 + (win.width() / 2.) - 5.),
-                        ..default()
-                    },
-                    ..default()
-                }),
-            VertexNumber,
-        ));
+        chosen_vertex
+    } else {
+        candidates[rng.gen_range(0..candidates.len())]
     }
 }
 
-// This resource stores the parameters used in the ant-colony-system equations for updating pheromones and probability calculation
-#[derive(Resource)]
-pub struct AntColonyParameters{
-    pub activate: bool,
-    pub number_of_ants: u32,
-    pub pheromone_constant: f32,
-    pub pheromone_evaporation_rate: f32,
-    pub alpha: f32,
-    pub beta: f32,
-    pub pheromone_matrix: DMatrix<f32>,
-    pub ant_paths: Vec<(DMatrix<f32>, f32)>,
+// Rotates the cycle so that it always starts on vertex 1, no matter where the ant was released or how local search moved it
+fn normalize_tour(
+    tour: &mut Vec<u32>,
+) {
+    if let Some(start_index) = tour.iter().position(|vertex| *vertex == 1)
+    {
+        tour.rotate_left(start_index);
+    }
 }
 
-// This system draws the shortest path using the Ant-Colony Optimization algorithm
-pub fn ant_colony_optimization(
-    mut ant_colony_parameters: ResMut<AntColonyParameters>,
-    vertex_list: Res<VertexList>,
-    adjacency_matrix: Res<AdjacencyMatrix>,
-    mut shortest_cycle: ResMut<ShortestCycle>,
-){
-    if ant_colony_parameters.activate == true
-    {
-        let count = vertex_list.count;
+// Applies the local search to every ant tour, the tours are split among the compute threads the same way as in release_ants.
+// The moves shorten the total length, so a tour is only replaced when that did not make it worse for the objective, for example its bottleneck
+fn improve_ant_paths(
+    ant_paths: &mut Vec<(Vec<u32>, f32)>,
+    local_search: local_search::LocalSearch,
+    solver_graph: &SolverGraph,
+    candidate_lists: &Vec<Vec<u32>>,
+    directed: bool,
+) {
+    let adjacency_matrix = &solver_graph.matrix;
+    let task_pool = ComputeTaskPool::get();
+    let chunk_size = ((ant_paths.len() + task_pool.thread_num() - 1) / task_pool.thread_num()).max(1);
 
-        let mut ant_paths = ant_colony_parameters.ant_paths.clone();
-        let pheromone_matrix = ant_colony_parameters.pheromone_matrix.clone();
-        
-        ant_colony_parameters.ant_paths = release_ants(
-            ant_colony_parameters.alpha,
-            ant_colony_parameters.beta,
-            &adjacency_matrix.matrix,
-            &pheromone_matrix,
-            ant_paths,
-            &count,
-            &ant_colony_parameters.number_of_ants,
-        );
+    task_pool.scope(|scope| {
+        for chunk in ant_paths.chunks_mut(chunk_size)
+        {
+            scope.spawn(async move {
+                for ant_path in chunk
+                {
+                    let mut tour = ant_path.0.clone();
 
-        ant_paths = ant_colony_parameters.ant_paths.clone();
+                    local_search::improve_tour(&mut tour, local_search, adjacency_matrix, candidate_lists, directed);
+                    normalize_tour(&mut tour);
 
-        ant_colony_parameters.pheromone_matrix = update_pheromones(
-            pheromone_matrix,
-            &ant_paths,
-            &ant_colony_parameters.pheromone_constant,
-            &ant_colony_parameters.pheromone_evaporation_rate,
-            &count,
-        );
+                    let tour_length = local_search::tour_length(&tour, adjacency_matrix);
 
-        shortest_cycle.vector = ant_paths_to_shortest_cycle(
-            &ant_paths,
-            &count,
-        )
-    }
+                    if tour_cost(&tour, tour_length, solver_graph) <= tour_cost(&ant_path.0, ant_path.1, solver_graph)
+                    {
+                        *ant_path = (tour, tour_length);
+                    }
+                }
+            });
+        }
+    });
 }
 
-
-// This function simulates ants walking through each vertex until a hamiltonian cycle is complete
-fn release_ants(
-    alpha: f32,
-    beta: f32,
-    adjacency_matrix: &DMatrix<f32>,
+// Average number of edges per vertex whose pheromone is at least lambda of the way between the weakest and the strongest edge of that vertex,
+// a value close to 2 means that the ants keep building the same tour
+fn lambda_branching_factor(
     pheromone_matrix: &DMatrix<f32>,
-    mut ant_paths: Vec<(DMatrix<f32>, f32)>,
-    vertex_count: &u32,
-    number_of_ants: &u32,
-) -> Vec<(DMatrix<f32>, f32)> {
-    let mut rng = rand::thread_rng();
-    let mut current_vertex: u32;
-    let mut previous_vertex: u32;
-    let mut ant_tour_length: f32;
-    let mut c: u32;
-    let mut distribution;
-    let mut probability_sum: f32;
+    lambda: f32,
+) -> f32 {
+    let vertex_count = pheromone_matrix.nrows();
+    let mut branches = 0;
 
-    for _i in 0..*number_of_ants
+    for j in 0..vertex_count
     {
-        let mut unvisited_vertices: Vec<u32> = Vec::new();
-        let mut ant_path = DMatrix::from_diagonal_element(*vertex_count as usize, *vertex_count as usize, 0.0);
-
-        current_vertex = 1;
-        ant_tour_length = 0.0;
+        let mut minimum = f32::INFINITY;
+        let mut maximum = f32::NEG_INFINITY;
 
-        for j in 1..*vertex_count
+        for i in 0..vertex_count
         {
-            unvisited_vertices.push(j + 1);
-            // println!("unvisited_vertices[{}] = {}", j - 1, unvisited_vertices[j as usize - 1]);
+            if i != j
+            {
+                minimum = minimum.min(pheromone_matrix[(j, i)]);
+                maximum = maximum.max(pheromone_matrix[(j, i)]);
+            }
         }
 
-        // println!("----------------------------------------");
+        let threshold = minimum + lambda * (maximum - minimum);
 
-        while unvisited_vertices.len() > 0
+        for i in 0..vertex_count
         {
-            let mut probability_list: Vec<f64> = Vec::new();
-            probability_sum = 0.0;
-
-            for j in 0..unvisited_vertices.len()
+            if i != j && pheromone_matrix[(j, i)] >= threshold
             {
-                /*println!("Information used in probability calculation: ");
-                println!("Amount of pheromones laid on edge {} - {} stored in the pheromone matrix: {}", unvisited_vertices[j], current_vertex, pheromone_matrix[(unvisited_vertices[j] as usize - 1, current_vertex as usize - 1)]);
-                println!("Weight assigned to edge {} - {} stored in the adjacency matrix: {}", unvisited_vertices[j], current_vertex, adjacency_matrix[(unvisited_vertices[j] as usize - 1, current_vertex as usize - 1)]);*/
-
-                probability_sum += f32::powf(pheromone_matrix[(unvisited_vertices[j] as usize - 1, current_vertex as usize - 1)],alpha) * (f32::powf(1.0 / adjacency_matrix[(unvisited_vertices[j] as usize - 1, current_vertex as usize - 1)],beta));
-            }
-
-            if probability_sum == 0.0 {
-                let equal_prob = 1.0 / unvisited_vertices.len() as f64;
-
-                for _j in 0..unvisited_vertices.len() {
-                    probability_list.push(equal_prob);
-                    // println!("Propability for vertex {}: {}", unvisited_vertices[j], equal_prob);
-                }
-            } else {
-                for j in 0..unvisited_vertices.len() {
-                    let prob: f64 = ((f32::powf(pheromone_matrix[(unvisited_vertices[j] as usize - 1, current_vertex as usize - 1)],alpha) * (f32::powf(1.0 / adjacency_matrix[(unvisited_vertices[j] as usize - 1, current_vertex as usize - 1)],beta))) / probability_sum) as f64;
-
-                    probability_list.push(prob);
-                    // println!("Probability for vertex {}: {}", unvisited_vertices[j], prob);
-                }
+                branches += 1;
             }
+        }
+    }
 
+    return branches as f32 / vertex_count as f32;
+}
 
-            distribution = WeightedIndex::new(&probability_list).unwrap();
-            c = distribution.sample(&mut rng) as u32;
-
+// Average number of edges by which two ant tours of the same iteration differ
+fn average_ant_distance(
+    ant_paths: &Vec<(Vec<u32>, f32)>,
+    directed: bool,
+) -> f32 {
+    if ant_paths.len() < 2
+    {
+        return 0.0;
+    }
 
-            previous_vertex = current_vertex;
-            current_vertex = unvisited_vertices[c as usize];
+    // Successors of every vertex in each tour, in undirected graphs an edge is shared when either direction matches
+    let successors: Vec<Vec<u32>> = ant_paths.iter().map(|ant_path| {
+        let mut successor = vec![0; ant_path.0.len()];
 
-            unvisited_vertices.remove(c as usize);
+        for i in 0..ant_path.0.len()
+        {
+            successor[ant_path.0[i] as usize - 1] = ant_path.0[(i + 1) % ant_path.0.len()];
+        }
 
-            ant_tour_length += adjacency_matrix[(previous_vertex as usize - 1, current_vertex as usize - 1)];
+        successor
+    }).collect();
 
-            ant_path[(previous_vertex as usize - 1, current_vertex as usize - 1)] = 1.0;
-            ant_path[(current_vertex as usize - 1 ,previous_vertex as usize - 1)] = 1.0;
+    let mut total_distance = 0;
+    let mut pairs = 0;
 
-            /*for j in 0..unvisited_vertices.len()
+    for a in 0..ant_paths.len()
+    {
+        for b in a + 1..ant_paths.len()
+        {
+            for vertex in 0..successors[a].len()
             {
-                println!("unvisited_vertices[{}] = {}", j, unvisited_vertices[j as usize]);
+                let next = successors[a][vertex];
+
+                if successors[b][vertex] != next && (directed == true || successors[b][next as usize - 1] != vertex as u32 + 1)
+                {
+                    total_distance += 1;
+                }
             }
 
-            println!("====================================================");*/
+            pairs += 1;
         }
+    }
+
+    return total_distance as f32 / pairs as f32;
+}
 
-        ant_tour_length += adjacency_matrix[(current_vertex as usize - 1, 0)];
+fn average_tour_cost(
+    ant_paths: &Vec<(Vec<u32>, f32)>,
+    solver_graph: &SolverGraph,
+) -> f32 {
+    ant_paths.iter().map(|ant_path| tour_cost(&ant_path.0, ant_path.1, solver_graph)).sum::<f32>() / ant_paths.len() as f32
+}
 
-        ant_path[(current_vertex as usize - 1, 0)] += 1.0;
-        ant_path[(0, current_vertex as usize - 1)] += 1.0;
+// Every candidate was already visited, so the ant moves to the unvisited vertex with the best choice information,
+// when only forbidden edges are left the ant has to take one of them and its tour becomes infinitely long
+fn fallback_vertex(
+    visited_vertices: &VisitedVertices,
+    column: usize,
+    choice_info: &DMatrix<f32>,
+    heuristic_matrix: &DMatrix<f32>,
+    vertex_count: &u32,
+) -> u32 {
+    let mut best_vertex = 0;
+    let mut best_value = (f32::NEG_INFINITY, f32::NEG_INFINITY);
 
-        /*for j in 0..*vertex_count
+    for vertex in 1..=*vertex_count
+    {
+        if !visited_vertices.contains(vertex)
         {
-            for x in 0..*vertex_count
+            let value = (choice_info[(column, vertex as usize - 1)], heuristic_matrix[(column, vertex as usize - 1)]);
+
+            if value > best_value
             {
-                print!("{} ,", ant_path[(x as usize, j as usize)]);
+                best_value = value;
+                best_vertex = vertex;
             }
-            println!();
-        }*/
+        }
+    }
+
+    return best_vertex;
+}
+
+// Computes the probabilities with which an ant that walked through tour_prefix chooses each of its next vertices, mirrors construct_ant_tour
+fn transition_probabilities(
+    tour_prefix: &[u32],
+    choice_info: &DMatrix<f32>,
+    heuristic_matrix: &DMatrix<f32>,
+    candidate_lists: &Vec<Vec<u32>>,
+    vertex_count: &u32,
+) -> Vec<(u32, f32)> {
+    let mut visited_vertices = VisitedVertices::new(*vertex_count);
+
+    for vertex in tour_prefix
+    {
+        visited_vertices.insert(*vertex);
+    }
+
+    let column = tour_prefix[tour_prefix.len() - 1] as usize - 1;
+    let candidates: Vec<u32> = candidate_lists[column].iter().copied()
+        .filter(|candidate| !visited_vertices.contains(*candidate) && heuristic_matrix[(column, *candidate as usize - 1)] > 0.0)
+        .collect();
+
+    if candidates.is_empty()
+    {
+        return vec![(fallback_vertex(&visited_vertices, column, choice_info, heuristic_matrix, vertex_count), 1.0)];
+    }
 
-        ant_paths.push((ant_path, ant_tour_length));
+    let probability_sum: f32 = candidates.iter().map(|candidate| choice_info[(column, *candidate as usize - 1)]).sum();
 
-        // println!("Ant tour length: {}" , ant_tour_length);
+    if probability_sum > 0.0
+    {
+        return candidates.iter().map(|candidate| (*candidate, choice_info[(column, *candidate as usize - 1)] / probability_sum)).collect();
     }
 
-    return ant_paths;
+    return candidates.iter().map(|candidate| (*candidate, 1.0 / candidates.len() as f32)).collect();
+}
+
+// Lays the given amount of pheromones on every edge of the tour, in both directions unless the graph is directed
+fn deposit_pheromones(
+    pheromone_matrix: &mut DMatrix<f32>,
+    tour: &Vec<u32>,
+    amount: f32,
+    directed: bool,
+) {
+    for i in 0..tour.len()
+    {
+        let vertex1 = tour[i] as usize - 1;
+        let vertex2 = tour[(i + 1) % tour.len()] as usize - 1;
+
+        pheromone_matrix[(vertex1, vertex2)] += amount;
+
+        if directed == false
+        {
+            pheromone_matrix[(vertex2, vertex1)] += amount;
+        }
+    }
 }
 
 // This function updates the pheromone amount laid on each edge based on the paths that the ants took
 fn update_pheromones(
     mut pheromone_matrix: DMatrix<f32>,
-    ant_paths: &Vec<(DMatrix<f32>, f32)>,
+    ant_paths: &Vec<(Vec<u32>, f32)>,
     pheromone_constant: &f32,
     pheromone_evaporation_rate: &f32,
-    vertex_count: &u32,
+    directed: bool,
 ) -> DMatrix<f32> {
-
-    for i in 0..ant_paths.len()
+    // The trails evaporate once for every ant before it deposits on its own tour
+    for ant_path in ant_paths
     {
-        let mut ant_pheromone_path = DMatrix::from_diagonal_element(*vertex_count as usize, *vertex_count as usize, 0.0);
+        pheromone_matrix *= 1.0 - pheromone_evaporation_rate;
+        deposit_pheromones(&mut pheromone_matrix, &ant_path.0, pheromone_constant / ant_path.1, directed);
+    }
 
-        for j in 0..*vertex_count
-        {
-            for x in 0..*vertex_count
-            {
-                if ant_paths[i].0[(x as usize, j as usize)] > 0.0
-                {
-                    ant_pheromone_path[(x as usize, j as usize)] = pheromone_constant / ant_paths[i].1;
-                }
-            }
-        }
+    return pheromone_matrix;
+}
 
-        pheromone_matrix = pheromone_matrix*(1.0 - pheromone_evaporation_rate) + ant_pheromone_path;
-    }
+// Elitist Ant System: every ant of the iteration deposits pheromones and the best-so-far tour receives an extra deposit weighted by e
+fn update_pheromones_elitist(
+    mut pheromone_matrix: DMatrix<f32>,
+    ant_paths: &Vec<(Vec<u32>, f32)>,
+    best_path: &(Vec<u32>, f32),
+    pheromone_constant: &f32,
+    pheromone_evaporation_rate: &f32,
+    elitist_weight: &f32,
+    directed: bool,
+) -> DMatrix<f32> {
+    pheromone_matrix *= 1.0 - pheromone_evaporation_rate;
 
-    /*for j in 0..*vertex_count
+    for ant_path in ant_paths
     {
-        for x in 0..*vertex_count
-        {
-            print!("{} ,", pheromone_matrix[(x as usize, j as usize)]);
-        }
-        println!();
+        deposit_pheromones(&mut pheromone_matrix, &ant_path.0, pheromone_constant / ant_path.1, directed);
     }
 
-    println!("-------------------------------------------------------------------");*/
+    deposit_pheromones(&mut pheromone_matrix, &best_path.0, elitist_weight * pheromone_constant / best_path.1, directed);
 
     return pheromone_matrix;
 }
 
+// Rank-based Ant System: only the w - 1 best ants of the iteration deposit pheromones weighted by (w - rank), the best-so-far tour deposits with weight w
+fn update_pheromones_rank_based(
+    mut pheromone_matrix: DMatrix<f32>,
+    ant_paths: &Vec<(Vec<u32>, f32)>,
+    best_path: &(Vec<u32>, f32),
+    pheromone_constant: &f32,
+    pheromone_evaporation_rate: &f32,
+    rank_size: &u32,
+    directed: bool,
+) -> DMatrix<f32> {
+    let mut ranked_ant_paths: Vec<&(Vec<u32>, f32)> = ant_paths.iter().collect();
+    ranked_ant_paths.sort_by(|a, b| a.1.total_cmp(&b.1));
 
-// Converts ant_paths into shortest_cycle
-fn ant_paths_to_shortest_cycle(
-    ant_paths: &Vec<(DMatrix<f32>, f32)>,
-    count: &u32,
-) -> Vec<(u32, u32)> {
-    let mut shortest_cycle = Vec::new();
-    for j in 0..*count {
-        for i in 0..*count {
-            if ant_paths[ant_paths.len() - 1].0[(i as usize, j as usize)] > 0.0 {
-                shortest_cycle.push((i as u32 + 1, j as u32 + 1));
-            }
-        }
-    }
-    remove_duplicates(shortest_cycle)
-}
+    pheromone_matrix *= 1.0 - pheromone_evaporation_rate;
 
-// Removes duplicates from a vector of tuples
-fn remove_duplicates(
-    vector: Vec<(u32, u32)>,
-) -> Vec<(u32, u32)> {
-    let mut clean_vector = Vec::new();
-    for elem in vector {
-        if !clean_vector.contains(&elem) {
-            clean_vector.push(elem);
+    for (rank, ant_path) in ranked_ant_paths.iter().enumerate()
+    {
+        if rank as u32 + 1 >= *rank_size
+        {
+            break;
         }
+
+        let weight = (*rank_size - (rank as u32 + 1)) as f32;
+        deposit_pheromones(&mut pheromone_matrix, &ant_path.0, weight * pheromone_constant / ant_path.1, directed);
     }
-    return clean_vector;
-}
\ No newline at end of file
+
+    deposit_pheromones(&mut pheromone_matrix, &best_path.0, *rank_size as f32 * pheromone_constant / best_path.1, directed);
+
+    return pheromone_matrix;
+}
@@ -0,0 +1,583 @@
+use nalgebra::DMatrix;
+
+// Local search applied to every tour the ants construct before the pheromones get updated
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum LocalSearch {
+    None,
+    // Replaces two edges by reversing the segment between them
+    TwoOpt,
+    // Moves segments of up to three vertices to a better position, possibly reversed
+    OrOpt,
+    // 2-opt moves combined with exchanges of two neighbouring segments, which replace three edges
+    ThreeOpt,
+}
+
+// Improvements smaller than this are treated as rounding errors, otherwise the search could cycle forever
+pub const EPSILON: f32 = 1e-3;
+
+// Improves the tour in place until no move of the chosen neighbourhood shortens it, candidate lists restrict which moves are tried,
+// in directed graphs the cost of walking a reversed segment backwards is taken into account
+pub fn improve_tour(
+    tour: &mut Vec<u32>,
+    local_search: LocalSearch,
+    adjacency_matrix: &DMatrix<f32>,
+    candidate_lists: &Vec<Vec<u32>>,
+    directed: bool,
+) {
+    match local_search {
+        LocalSearch::None => {}
+        LocalSearch::TwoOpt => {
+            while two_opt(tour, adjacency_matrix, candidate_lists, directed) {}
+        }
+        LocalSearch::OrOpt => {
+            while or_opt(tour, adjacency_matrix, candidate_lists, directed) {}
+        }
+        LocalSearch::ThreeOpt => {
+            loop
+            {
+                while two_opt(tour, adjacency_matrix, candidate_lists, directed) {}
+
+                if !segment_exchange(tour, adjacency_matrix, candidate_lists)
+                {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+// Compares two tours of a constrained problem given as (penalty for the violated constraints, length), the penalty decides first
+pub fn is_better_constrained(
+    new_cost: (f32, f32),
+    old_cost: (f32, f32),
+) -> bool {
+    new_cost.0 < old_cost.0 - EPSILON || (new_cost.0 <= old_cost.0 + EPSILON && new_cost.1 < old_cost.1 - EPSILON)
+}
+
+// Segments are only moved and reversed within this many positions, the cost function of a constrained problem takes linear time,
+// so trying every pair of positions would block the window on larger graphs
+const CONSTRAINED_MOVE_RANGE: usize = 25;
+
+// Upper bound on the passes of the constrained search, a pass that lowers the penalty only a little can otherwise be followed by many more
+const CONSTRAINED_MAX_PASSES: usize = 50;
+
+// Moves segments of up to three vertices to another position and reverses parts of the tour while the cost of the tour improves,
+// meant for constraints that depend on the order of the whole tour, so the moves are evaluated by the cost function from scratch,
+// the second part of the cost has to be the length of the tour, once no constraint is violated only moves that shorten the tour are evaluated,
+// the first vertex always stays in place
+pub fn improve_constrained_tour<F: Fn(&Vec<u32>) -> (f32, f32)>(
+    tour: &mut Vec<u32>,
+    adjacency_matrix: &DMatrix<f32>,
+    cost_function: F,
+) {
+    let n = tour.len();
+    let mut cost = cost_function(tour);
+    let mut improved = true;
+    let mut passes = 0;
+
+    // A move of a feasible tour can only be better when it is shorter, which is known from the changed edges alone
+    let can_skip = |cost: (f32, f32), delta: f32| cost.0 <= EPSILON && cost.1.is_finite() && !(delta < -EPSILON);
+
+    while improved && passes < CONSTRAINED_MAX_PASSES
+    {
+        improved = false;
+        passes += 1;
+
+        for segment_length in 1..=3
+        {
+            for i in 1..n
+            {
+                if i + segment_length > n
+                {
+                    break;
+                }
+
+                for j in i.saturating_sub(CONSTRAINED_MOVE_RANGE).max(1)..=(i + CONSTRAINED_MOVE_RANGE).min(n - segment_length)
+                {
+                    if j == i || can_skip(cost, segment_move_delta(tour, i, segment_length, j, adjacency_matrix))
+                    {
+                        continue;
+                    }
+
+                    let mut new_tour = tour.clone();
+                    let segment: Vec<u32> = new_tour.drain(i..i + segment_length).collect();
+                    new_tour.splice(j..j, segment);
+
+                    let new_cost = cost_function(&new_tour);
+
+                    if is_better_constrained(new_cost, cost)
+                    {
+                        *tour = new_tour;
+                        cost = new_cost;
+                        improved = true;
+                    }
+                }
+            }
+        }
+
+        for i in 1..n
+        {
+            for j in i + 1..(i + CONSTRAINED_MOVE_RANGE + 1).min(n)
+            {
+                if can_skip(cost, reversal_delta(tour, i, j, adjacency_matrix))
+                {
+                    continue;
+                }
+
+                let mut new_tour = tour.clone();
+                new_tour[i..=j].reverse();
+
+                let new_cost = cost_function(&new_tour);
+
+                if is_better_constrained(new_cost, cost)
+                {
+                    *tour = new_tour;
+                    cost = new_cost;
+                    improved = true;
+                }
+            }
+        }
+    }
+}
+
+// Change of the length of the closed tour when the segment of the given length starting at index i is removed
+// and inserted before index j of the remaining tour
+fn segment_move_delta(
+    tour: &Vec<u32>,
+    i: usize,
+    segment_length: usize,
+    j: usize,
+    adjacency_matrix: &DMatrix<f32>,
+) -> f32 {
+    let n = tour.len();
+    let remaining = |k: usize| if k < i { tour[k] } else { tour[(k + segment_length) % n] };
+
+    let (first, last) = (tour[i], tour[i + segment_length - 1]);
+    let (previous, next) = (tour[i - 1], tour[(i + segment_length) % n]);
+    let (before, after) = (remaining(j - 1), remaining(j % (n - segment_length)));
+
+    distance(adjacency_matrix, previous, next) - distance(adjacency_matrix, previous, first) - distance(adjacency_matrix, last, next)
+        + distance(adjacency_matrix, before, first) + distance(adjacency_matrix, last, after) - distance(adjacency_matrix, before, after)
+}
+
+// Change of the length of the closed tour when the vertices from index i to index j are reversed,
+// the edges inside the segment only change in directed graphs
+fn reversal_delta(
+    tour: &Vec<u32>,
+    i: usize,
+    j: usize,
+    adjacency_matrix: &DMatrix<f32>,
+) -> f32 {
+    let (previous, next) = (tour[i - 1], tour[(j + 1) % tour.len()]);
+    let mut delta = distance(adjacency_matrix, previous, tour[j]) + distance(adjacency_matrix, tour[i], next)
+        - distance(adjacency_matrix, previous, tour[i]) - distance(adjacency_matrix, tour[j], next);
+
+    for k in i..j
+    {
+        delta += distance(adjacency_matrix, tour[k + 1], tour[k]) - distance(adjacency_matrix, tour[k], tour[k + 1]);
+    }
+
+    return delta;
+}
+
+// Returns the length of the closed tour
+pub fn tour_length(
+    tour: &Vec<u32>,
+    adjacency_matrix: &DMatrix<f32>,
+) -> f32 {
+    let mut length = 0.0;
+
+    for i in 0..tour.len()
+    {
+        length += distance(adjacency_matrix, tour[i], tour[(i + 1) % tour.len()]);
+    }
+
+    return length;
+}
+
+// Position in the closed tour at which inserting the vertex adds the least length together with the added length,
+// the vertex is inserted before the returned position
+pub fn cheapest_insertion(
+    tour: &Vec<u32>,
+    vertex: u32,
+    adjacency_matrix: &DMatrix<f32>,
+) -> (usize, f32) {
+    if tour.is_empty()
+    {
+        return (0, 0.0);
+    }
+
+    let mut best_insertion = (tour.len(), f32::INFINITY);
+
+    for i in 0..tour.len()
+    {
+        let (previous, next) = (tour[i], tour[(i + 1) % tour.len()]);
+        let added_length = distance(adjacency_matrix, previous, vertex) + distance(adjacency_matrix, vertex, next) - distance(adjacency_matrix, previous, next);
+
+        if added_length < best_insertion.1
+        {
+            best_insertion = (i + 1, added_length);
+        }
+    }
+
+    return best_insertion;
+}
+
+fn distance(
+    adjacency_matrix: &DMatrix<f32>,
+    vertex1: u32,
+    vertex2: u32,
+) -> f32 {
+    adjacency_matrix[(vertex1 as usize - 1, vertex2 as usize - 1)]
+}
+
+// Stores the index of every vertex inside the tour
+fn tour_positions(
+    tour: &Vec<u32>,
+) -> Vec<usize> {
+    let mut positions = vec![0; tour.len()];
+
+    for i in 0..tour.len()
+    {
+        positions[tour[i] as usize - 1] = i;
+    }
+
+    return positions;
+}
+
+// Reverses the part of the tour between positions i + 1 and j, wrapping around the end of the tour when needed
+fn reverse_segment(
+    tour: &mut Vec<u32>,
+    i: usize,
+    j: usize,
+    directed: bool,
+) {
+    let n = tour.len();
+
+    if directed == true
+    {
+        // Reversing the complementary segment would also reverse the direction of the whole cycle
+        let length = (j + n - i) % n;
+
+        for k in 0..length / 2
+        {
+            tour.swap((i + 1 + k) % n, (i + length - k) % n);
+        }
+    } else if i < j {
+        tour[i + 1..=j].reverse();
+    } else {
+        // Reversing the complementary segment results in the same cycle and never wraps around
+        tour[j + 1..=i].reverse();
+    }
+}
+
+// Change in length caused by walking the segment between positions i + 1 and j backwards, always 0 in undirected graphs
+fn reversal_cost(
+    tour: &Vec<u32>,
+    i: usize,
+    j: usize,
+    adjacency_matrix: &DMatrix<f32>,
+    directed: bool,
+) -> f32 {
+    if directed == false
+    {
+        return 0.0;
+    }
+
+    let n = tour.len();
+    let length = (j + n - i) % n;
+    let mut cost = 0.0;
+
+    for k in 1..length
+    {
+        let vertex1 = tour[(i + k) % n];
+        let vertex2 = tour[(i + k + 1) % n];
+
+        cost += distance(adjacency_matrix, vertex2, vertex1) - distance(adjacency_matrix, vertex1, vertex2);
+    }
+
+    return cost;
+}
+
+// One pass of 2-opt over the whole tour, returns whether the tour got shorter
+fn two_opt(
+    tour: &mut Vec<u32>,
+    adjacency_matrix: &DMatrix<f32>,
+    candidate_lists: &Vec<Vec<u32>>,
+    directed: bool,
+) -> bool {
+    let n = tour.len();
+
+    if n < 4
+    {
+        return false;
+    }
+
+    let mut positions = tour_positions(tour);
+    let mut improved = false;
+
+    for i in 0..n
+    {
+        let a = tour[i];
+        let successor = tour[(i + 1) % n];
+        let predecessor = tour[(i + n - 1) % n];
+
+        for c in &candidate_lists[a as usize - 1]
+        {
+            let c = *c;
+            let d_ac = distance(adjacency_matrix, a, c);
+
+            if d_ac >= distance(adjacency_matrix, a, successor) && d_ac >= distance(adjacency_matrix, predecessor, a)
+            {
+                break;
+            }
+
+            let j = positions[c as usize - 1];
+
+            // New edges a - c and successor(a) - successor(c)
+            let c_successor = tour[(j + 1) % n];
+
+            if c != successor && c_successor != a
+            {
+                let delta = d_ac + distance(adjacency_matrix, successor, c_successor)
+                    - distance(adjacency_matrix, a, successor)
+                    - distance(adjacency_matrix, c, c_successor)
+                    + reversal_cost(tour, i, j, adjacency_matrix, directed);
+
+                if delta < -EPSILON
+                {
+                    reverse_segment(tour, i, j, directed);
+                    positions = tour_positions(tour);
+                    improved = true;
+                    break;
+                }
+            }
+
+            // New edges a - c and predecessor(a) - predecessor(c)
+            let c_predecessor = tour[(j + n - 1) % n];
+
+            if c != predecessor && c_predecessor != a
+            {
+                let delta = d_ac + distance(adjacency_matrix, predecessor, c_predecessor)
+                    - distance(adjacency_matrix, predecessor, a)
+                    - distance(adjacency_matrix, c_predecessor, c)
+                    + reversal_cost(tour, (i + n - 1) % n, (j + n - 1) % n, adjacency_matrix, directed);
+
+                if delta < -EPSILON
+                {
+                    reverse_segment(tour, (i + n - 1) % n, (j + n - 1) % n, directed);
+                    positions = tour_positions(tour);
+                    improved = true;
+                    break;
+                }
+            }
+        }
+    }
+
+    return improved;
+}
+
+// One pass of Or-opt over the whole tour, returns whether the tour got shorter
+fn or_opt(
+    tour: &mut Vec<u32>,
+    adjacency_matrix: &DMatrix<f32>,
+    candidate_lists: &Vec<Vec<u32>>,
+    directed: bool,
+) -> bool {
+    let n = tour.len();
+    let mut positions = tour_positions(tour);
+    let mut improved = false;
+
+    for segment_length in 1..=3
+    {
+        if n < segment_length + 3
+        {
+            break;
+        }
+
+        for i in 0..n
+        {
+            if try_move_segment(tour, &positions, i, segment_length, adjacency_matrix, candidate_lists, directed)
+            {
+                positions = tour_positions(tour);
+                improved = true;
+            }
+        }
+    }
+
+    return improved;
+}
+
+// Tries to move the segment starting at position i between two neighbouring vertices, returns whether it was moved,
+// segments are only inserted reversed in undirected graphs
+fn try_move_segment(
+    tour: &mut Vec<u32>,
+    positions: &Vec<usize>,
+    i: usize,
+    segment_length: usize,
+    adjacency_matrix: &DMatrix<f32>,
+    candidate_lists: &Vec<Vec<u32>>,
+    directed: bool,
+) -> bool {
+    let n = tour.len();
+
+    let previous = tour[(i + n - 1) % n];
+    let first = tour[i];
+    let last = tour[(i + segment_length - 1) % n];
+    let next = tour[(i + segment_length) % n];
+
+    let removal_gain = distance(adjacency_matrix, previous, first)
+        + distance(adjacency_matrix, last, next)
+        - distance(adjacency_matrix, previous, next);
+
+    if removal_gain <= EPSILON
+    {
+        return false;
+    }
+
+    let in_segment = |vertex: u32| (positions[vertex as usize - 1] + n - i) % n < segment_length;
+
+    for end in [first, last]
+    {
+        for c in &candidate_lists[end as usize - 1]
+        {
+            let c = *c;
+
+            if distance(adjacency_matrix, end, c) >= removal_gain
+            {
+                break;
+            }
+
+            if in_segment(c)
+            {
+                continue;
+            }
+
+            let j = positions[c as usize - 1];
+
+            for (x, y) in [(c, tour[(j + 1) % n]), (tour[(j + n - 1) % n], c)]
+            {
+                if in_segment(x) || in_segment(y)
+                {
+                    continue;
+                }
+
+                let removed_edge = distance(adjacency_matrix, x, y);
+                let forward = distance(adjacency_matrix, x, first) + distance(adjacency_matrix, last, y) - removed_edge;
+                let reversed = if directed == true {
+                    f32::INFINITY
+                } else {
+                    distance(adjacency_matrix, x, last) + distance(adjacency_matrix, first, y) - removed_edge
+                };
+
+                if forward.min(reversed) - removal_gain < -EPSILON
+                {
+                    let mut segment: Vec<u32> = (0..segment_length).map(|k| tour[(i + k) % n]).collect();
+
+                    if reversed < forward
+                    {
+                        segment.reverse();
+                    }
+
+                    // The rest of the tour starts right after the segment, so x and y stay neighbours
+                    let mut new_tour: Vec<u32> = (segment_length..n).map(|k| tour[(i + k) % n]).collect();
+                    let x_position = new_tour.iter().position(|vertex| *vertex == x).unwrap();
+
+                    new_tour.splice(x_position + 1..x_position + 1, segment);
+                    *tour = new_tour;
+
+                    return true;
+                }
+            }
+        }
+    }
+
+    return false;
+}
+
+// One pass of the segment exchange move, which turns A B C D into A C B D without reversing anything, returns whether the tour got shorter
+fn segment_exchange(
+    tour: &mut Vec<u32>,
+    adjacency_matrix: &DMatrix<f32>,
+    candidate_lists: &Vec<Vec<u32>>,
+) -> bool {
+    let n = tour.len();
+
+    if n < 5
+    {
+        return false;
+    }
+
+    let mut positions = tour_positions(tour);
+    let mut improved = false;
+
+    for i in 0..n
+    {
+        let t1 = tour[i];
+        let t2 = tour[(i + 1) % n];
+        let d12 = distance(adjacency_matrix, t1, t2);
+
+        // Order of the vertices counted from t2, t1 itself is the last one
+        let relative = |positions: &Vec<usize>, vertex: u32| (positions[vertex as usize - 1] + n - (i + 1)) % n;
+
+        'search: for t4 in &candidate_lists[t1 as usize - 1]
+        {
+            let t4 = *t4;
+            let gain1 = d12 - distance(adjacency_matrix, t1, t4);
+
+            if gain1 <= 0.0
+            {
+                break;
+            }
+
+            let r4 = relative(&positions, t4);
+
+            // Segment B = t2 .. t3 has to be non-empty and segment C has to start before t1
+            if r4 < 1 || r4 >= n - 1
+            {
+                continue;
+            }
+
+            let t3 = tour[(i + r4) % n];
+
+            for t5 in &candidate_lists[t2 as usize - 1]
+            {
+                let t5 = *t5;
+                let gain2 = gain1 + distance(adjacency_matrix, t3, t4) - distance(adjacency_matrix, t5, t2);
+
+                if gain2 <= 0.0
+                {
+                    break;
+                }
+
+                let r5 = relative(&positions, t5);
+
+                if r5 < r4 || r5 >= n - 1
+                {
+                    continue;
+                }
+
+                let t6 = tour[(i + r5 + 2) % n];
+                let delta = distance(adjacency_matrix, t3, t6) - distance(adjacency_matrix, t5, t6) - gain2;
+
+                if delta < -EPSILON
+                {
+                    let sequence: Vec<u32> = (0..n).map(|k| tour[(i + 1 + k) % n]).collect();
+
+                    let mut new_tour = Vec::with_capacity(n);
+                    new_tour.extend_from_slice(&sequence[r4..=r5]);
+                    new_tour.extend_from_slice(&sequence[0..r4]);
+                    new_tour.extend_from_slice(&sequence[r5 + 1..]);
+
+                    *tour = new_tour;
+                    positions = tour_positions(tour);
+                    improved = true;
+
+                    break 'search;
+                }
+            }
+        }
+    }
+
+    return improved;
+}
@@ -0,0 +1,309 @@
+use crate::app;
+use crate::graph;
+use crate::local_search;
+use crate::vehicle_routing;
+
+use bevy::prelude::*;
+use bevy_prototype_debug_lines::*;
+use nalgebra::DMatrix;
+
+// This resource stores the precedence constraints together with the pickup and delivery pairs, the tour starts on the depot
+// of the graph settings with an empty vehicle and the loads are the demands of the pickup vertices
+#[derive(Resource)]
+pub struct PrecedenceConstraints {
+    // (before, after) pairs, the first vertex has to be visited before the second one
+    pub constraints: Vec<(u32, u32)>,
+    // (pickup, delivery) pairs, the demand of the pickup vertex is loaded there and unloaded on the delivery vertex
+    pub pairs: Vec<(u32, u32)>,
+    pub arrows: bool,
+    pub depot: u32,
+    pub solved: bool,
+}
+
+impl PrecedenceConstraints {
+    // Every pickup has to happen before its delivery, so the pairs are precedence constraints as well
+    fn precedences(&self) -> Vec<(u32, u32)> {
+        self.constraints.iter().chain(self.pairs.iter()).copied().collect()
+    }
+}
+
+// Position of every vertex in the tour, vertices that are not visited get None
+fn tour_positions(
+    tour: &Vec<u32>,
+    vertex_count: usize,
+) -> Vec<Option<usize>> {
+    let mut positions = vec![None; vertex_count];
+
+    for (position, vertex) in tour.iter().enumerate()
+    {
+        positions[*vertex as usize - 1] = Some(position);
+    }
+
+    return positions;
+}
+
+// Precedences whose second vertex is visited before the first one, or which contain a vertex that is not visited at all
+fn violated_precedences(
+    tour: &Vec<u32>,
+    precedence_constraints: &PrecedenceConstraints,
+    vertex_count: usize,
+) -> Vec<(u32, u32)> {
+    let positions = tour_positions(tour, vertex_count);
+
+    precedence_constraints.precedences().into_iter()
+        .filter(|(before, after)| match (positions[*before as usize - 1], positions[*after as usize - 1]) {
+            (Some(before_position), Some(after_position)) => before_position > after_position,
+            _ => true,
+        })
+        .collect()
+}
+
+// Load of the vehicle after leaving every vertex of the tour
+fn vehicle_loads(
+    tour: &Vec<u32>,
+    precedence_constraints: &PrecedenceConstraints,
+    demands: &Vec<u32>,
+) -> Vec<i64> {
+    let mut load_changes = vec![0; demands.len()];
+    let mut load = 0;
+
+    // The pairs are summed up per vertex once, so the loads take a single pass over the tour
+    for (pickup, delivery) in &precedence_constraints.pairs
+    {
+        load_changes[*pickup as usize - 1] += demands[*pickup as usize - 1] as i64;
+        load_changes[*delivery as usize - 1] -= demands[*pickup as usize - 1] as i64;
+    }
+
+    tour.iter()
+        .map(|vertex| {
+            load += load_changes[*vertex as usize - 1];
+            load
+        })
+        .collect()
+}
+
+// Tours are compared by the number of violated precedences plus the load exceeding the capacity first and by their length second
+fn tour_cost(
+    tour: &Vec<u32>,
+    adjacency_matrix: &DMatrix<f32>,
+    precedence_constraints: &PrecedenceConstraints,
+    demands: &Vec<u32>,
+    capacity: u32,
+) -> (f32, f32) {
+    let violations = violated_precedences(tour, precedence_constraints, adjacency_matrix.nrows()).len() as f32;
+    let overload: i64 = vehicle_loads(tour, precedence_constraints, demands).iter().map(|load| (load - capacity as i64).max(0)).sum();
+
+    (violations + overload as f32, local_search::tour_length(tour, adjacency_matrix))
+}
+
+// Reads the tour out of the shortest cycle starting on the depot
+fn stored_tour(
+    shortest_cycle: &graph::ShortestCycle,
+    depot: u32,
+) -> Vec<u32> {
+    let mut tour: Vec<u32> = shortest_cycle.vector.iter().map(|(vertex, _)| *vertex).collect();
+
+    if let Some(depot_position) = tour.iter().position(|vertex| *vertex == depot)
+    {
+        tour.rotate_left(depot_position);
+    }
+
+    return tour;
+}
+
+// Checks the precedence constraints, the pickup and delivery pairs and the capacity on the tour stored in the shortest cycle,
+// returns the description of every violated constraint
+pub fn validate_precedence(
+    shortest_cycle: &graph::ShortestCycle,
+    precedence_constraints: &PrecedenceConstraints,
+    vertex_list: &graph::VertexList,
+    capacity: u32,
+) -> Vec<String> {
+    let tour = stored_tour(shortest_cycle, precedence_constraints.depot);
+    let positions = tour_positions(&tour, vertex_list.vector.len());
+    let mut violations = Vec::new();
+
+    for vertex in 1..=vertex_list.count
+    {
+        let visits = tour.iter().filter(|tour_vertex| **tour_vertex == vertex).count();
+
+        if visits != 1
+        {
+            violations.push(format!("vertex {} visited {} times instead of once", vertex, visits));
+        }
+    }
+
+    for (before, after) in violated_precedences(&tour, precedence_constraints, vertex_list.vector.len())
+    {
+        if positions[before as usize - 1].is_some() && positions[after as usize - 1].is_some()
+        {
+            let kind = if precedence_constraints.pairs.contains(&(before, after)) { "delivered" } else { "visited" };
+
+            violations.push(format!("{} {} before {}", after, kind, before));
+        }
+    }
+
+    for (position, load) in vehicle_loads(&tour, precedence_constraints, &vertex_list.demands).iter().enumerate()
+    {
+        if *load > capacity as i64
+        {
+            violations.push(format!("load {} exceeds the capacity {} after vertex {}", load, capacity, tour[position]));
+        }
+    }
+
+    return violations;
+}
+
+// Searches for the shortest tour from the depot that respects every precedence and never exceeds the capacity, the nearest vertex whose
+// predecessors were all visited is chosen as the next one, then segments of the tour are moved and reversed while the tour improves
+pub fn solve_precedence(
+    precedence_constraints: &mut PrecedenceConstraints,
+    adjacency_matrix: &DMatrix<f32>,
+    vertex_list: &graph::VertexList,
+    graph_settings: &graph::GraphSettings,
+    capacity: u32,
+    shortest_cycle: &mut graph::ShortestCycle,
+) {
+    shortest_cycle.vector = Vec::new();
+    shortest_cycle.total_cycle_weight = 0.0;
+    shortest_cycle.routes = Vec::new();
+    shortest_cycle.skipped = Vec::new();
+    precedence_constraints.solved = false;
+
+    if vertex_list.count < 2 || graph_settings.depot < 1 || graph_settings.depot > vertex_list.count
+    {
+        println!("precedence: the graph needs a depot and at least one other vertex");
+        return;
+    }
+
+    let depot = graph_settings.depot;
+    let demands = &vertex_list.demands;
+    let precedences = precedence_constraints.precedences();
+
+    let mut tour = vec![depot];
+    let mut visited = vec![false; vertex_list.vector.len()];
+    let mut load: i64 = 0;
+    visited[depot as usize - 1] = true;
+
+    while tour.len() < vertex_list.vector.len()
+    {
+        let last = tour[tour.len() - 1];
+
+        let added_load = |vertex: u32| -> i64 {
+            precedence_constraints.pairs.iter()
+                .map(|(pickup, delivery)| {
+                    if *pickup == vertex { demands[*pickup as usize - 1] as i64 } else if *delivery == vertex { -(demands[*pickup as usize - 1] as i64) } else { 0 }
+                })
+                .sum()
+        };
+
+        let available = |vertex: &u32| !visited[*vertex as usize - 1]
+            && precedences.iter().all(|(before, after)| after != vertex || visited[*before as usize - 1]);
+
+        let nearest = |candidates: Vec<u32>| candidates.into_iter()
+            .min_by(|a, b| adjacency_matrix[(last as usize - 1, *a as usize - 1)].total_cmp(&adjacency_matrix[(last as usize - 1, *b as usize - 1)]));
+
+        let unvisited: Vec<u32> = (1..=vertex_list.count).filter(|vertex| !visited[*vertex as usize - 1]).collect();
+
+        // Vertices that fit into the vehicle come first, a cycle of precedences leaves no vertex available and is broken anywhere
+        let next = nearest(unvisited.iter().copied().filter(|vertex| available(vertex) && load + added_load(*vertex) <= capacity as i64).collect())
+            .or_else(|| nearest(unvisited.iter().copied().filter(|vertex| available(vertex)).collect()))
+            .or_else(|| nearest(unvisited.clone()))
+            .unwrap();
+
+        load += added_load(next);
+        visited[next as usize - 1] = true;
+        tour.push(next);
+    }
+
+    local_search::improve_constrained_tour(&mut tour, adjacency_matrix, |tour| tour_cost(tour, adjacency_matrix, precedence_constraints, demands, capacity));
+
+    let (penalty, length) = tour_cost(&tour, adjacency_matrix, precedence_constraints, demands, capacity);
+
+    for i in 0..tour.len()
+    {
+        shortest_cycle.vector.push((tour[i], tour[(i + 1) % tour.len()]));
+    }
+
+    shortest_cycle.routes.push((tour.len(), length));
+    shortest_cycle.total_cycle_weight = length;
+
+    if penalty > 0.0
+    {
+        println!("precedence: no tour respecting every constraint found, tour {:?} with length {}", tour, length);
+    } else {
+        println!("precedence: tour {:?} with length {}", tour, length);
+    }
+
+    precedence_constraints.depot = depot;
+    precedence_constraints.solved = true;
+
+    // The validator result is printed once here, the draw system only keeps the text on screen up to date
+    for violation in validate_precedence(shortest_cycle, precedence_constraints, vertex_list, capacity)
+    {
+        println!("precedence: {}", violation);
+    }
+}
+
+// This system draws every precedence as an arrow pointing from the earlier to the later vertex, pickup and delivery pairs in green,
+// the precedences violated by the tour of the precedence solver in red, and reports the result of the validator
+pub fn draw_precedence(
+    mut lines: ResMut<DebugLines>,
+    vertex_list: Res<graph::VertexList>,
+    shortest_cycle: Res<graph::ShortestCycle>,
+    precedence_constraints: Res<PrecedenceConstraints>,
+    vehicle_routing: Res<vehicle_routing::VehicleRouting>,
+    mut ant_colony_stats_text: Query<&mut Text, With<app::AntColonyStatsText>>,
+) {
+    let violated = if precedence_constraints.solved == true {
+        violated_precedences(&stored_tour(&shortest_cycle, precedence_constraints.depot), &precedence_constraints, vertex_list.vector.len())
+    } else {
+        Vec::new()
+    };
+
+    if precedence_constraints.arrows == true
+    {
+        for (before, after) in precedence_constraints.precedences()
+        {
+            let (_, y1, x1) = vertex_list.vector[before as usize - 1];
+            let (_, y2, x2) = vertex_list.vector[after as usize - 1];
+
+            // The arrows end at the border of the vertex circles
+            let start = Vec3::new(x1, y1, 0.);
+            let end = Vec3::new(x2, y2, 0.);
+            let direction = (end - start).normalize_or_zero();
+
+            let color = if violated.contains(&(before, after)) {
+                Color::RED
+            } else if precedence_constraints.pairs.contains(&(before, after)) {
+                Color::GREEN
+            } else {
+                Color::CYAN
+            };
+
+            lines.line_colored(start + direction * 18., end - direction * 18., 0.0, color);
+
+            let normal = Vec3::new(-direction.y, direction.x, 0.);
+            let tip = end - direction * 18.;
+
+            lines.line_colored(tip, tip - direction * 12. + normal * 6., 0.0, color);
+            lines.line_colored(tip, tip - direction * 12. - normal * 6., 0.0, color);
+        }
+    }
+
+    if precedence_constraints.solved == true && (precedence_constraints.is_changed() || vertex_list.is_changed() || vehicle_routing.is_changed())
+    {
+        let violations = validate_precedence(&shortest_cycle, &precedence_constraints, &vertex_list, vehicle_routing.capacity);
+
+        let stats = if violations.is_empty() {
+            format!("Precedence: tour length {:.1}, all precedences and the capacity respected", shortest_cycle.total_cycle_weight)
+        } else {
+            format!("Precedence: tour length {:.1}, constraints violated\n{}", shortest_cycle.total_cycle_weight, violations.join("\n"))
+        };
+
+        for mut text in &mut ant_colony_stats_text
+        {
+            text.sections[0].value = stats.clone();
+        }
+    }
+}
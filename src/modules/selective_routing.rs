@@ -0,0 +1,358 @@
+use crate::app;
+use crate::graph;
+use crate::local_search;
+use crate::vehicle_routing;
+
+use bevy::prelude::*;
+use bevy_despawn_with::DespawnAllCommandsExt;
+use nalgebra::DMatrix;
+
+// This resource stores the parameters of the selective routing problems together with their last solution, the tour starts on the depot
+// of the graph settings and the prizes are stored in the vertex list
+#[derive(Resource)]
+pub struct SelectiveRouting {
+    // Maximum tour length of the orienteering problem
+    pub budget: f32,
+    pub mode: SelectiveRoutingMode,
+    // Vertices visited after the depot in the order they are visited
+    pub route: Vec<u32>,
+    pub depot: u32,
+    pub solved: bool,
+}
+
+// Routing problems in which the salesman does not have to visit every vertex
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SelectiveRoutingMode {
+    // Collect as much prize as possible with a tour no longer than the budget
+    Orienteering,
+    // Minimize the tour length plus the prizes of the skipped vertices, which are paid as penalties
+    PrizeCollecting,
+}
+
+// Solves the chosen selective routing problem on the current graph, the skipped vertices are stored in the shortest cycle so that they are greyed out
+pub fn solve_selective_routing(
+    selective_routing: &mut SelectiveRouting,
+    mode: SelectiveRoutingMode,
+    adjacency_matrix: &DMatrix<f32>,
+    vertex_list: &graph::VertexList,
+    graph_settings: &graph::GraphSettings,
+    shortest_cycle: &mut graph::ShortestCycle,
+) {
+    shortest_cycle.vector = Vec::new();
+    shortest_cycle.total_cycle_weight = 0.0;
+    shortest_cycle.routes = Vec::new();
+    shortest_cycle.skipped = Vec::new();
+    selective_routing.route = Vec::new();
+    selective_routing.solved = false;
+
+    if vertex_list.count < 2 || graph_settings.depot < 1 || graph_settings.depot > vertex_list.count
+    {
+        println!("selective routing: the graph needs a depot and at least one other vertex");
+        return;
+    }
+
+    let depot = graph_settings.depot;
+    let prizes = &vertex_list.prizes;
+
+    let route = match mode {
+        SelectiveRoutingMode::Orienteering => orienteering(adjacency_matrix, prizes, depot, selective_routing.budget, graph_settings.directed),
+        SelectiveRoutingMode::PrizeCollecting => prize_collecting(adjacency_matrix, prizes, depot, graph_settings.directed),
+    };
+
+    let length = vehicle_routing::route_length(&route, adjacency_matrix, depot);
+
+    if route.len() > 0
+    {
+        let mut previous = depot;
+
+        for vertex in route.iter().copied().chain(std::iter::once(depot))
+        {
+            shortest_cycle.vector.push((previous, vertex));
+            previous = vertex;
+        }
+
+        shortest_cycle.routes.push((route.len() + 1, length));
+        shortest_cycle.total_cycle_weight = length;
+    }
+
+    shortest_cycle.skipped = (1..=vertex_list.count).filter(|vertex| *vertex != depot && !route.contains(vertex)).collect();
+
+    println!(
+        "{:?}: visited {:?} with length {}, skipped {:?}",
+        mode,
+        route,
+        length,
+        shortest_cycle.skipped,
+    );
+
+    selective_routing.route = route;
+    selective_routing.mode = mode;
+    selective_routing.depot = depot;
+    selective_routing.solved = true;
+}
+
+// Cheapest position for inserting the vertex into the route together with the length it adds, position 0 lies right after the depot
+fn cheapest_insertion(
+    route: &Vec<u32>,
+    vertex: u32,
+    adjacency_matrix: &DMatrix<f32>,
+    depot: u32,
+) -> (usize, f32) {
+    let tour: Vec<u32> = std::iter::once(depot).chain(route.iter().copied()).collect();
+    let (position, added_length) = local_search::cheapest_insertion(&tour, vertex, adjacency_matrix);
+
+    (position - 1, added_length)
+}
+
+fn prize_sum(
+    route: &Vec<u32>,
+    prizes: &Vec<f32>,
+) -> f32 {
+    route.iter().map(|vertex| prizes[*vertex as usize - 1]).sum()
+}
+
+// Inserts the vertices with the best ratio of prize to added length as long as the tour fits into the budget, then shortens the tour
+// and tries to replace visited vertices by more valuable ones, until neither adds any prize
+fn orienteering(
+    adjacency_matrix: &DMatrix<f32>,
+    prizes: &Vec<f32>,
+    depot: u32,
+    budget: f32,
+    directed: bool,
+) -> Vec<u32> {
+    let vertex_count = adjacency_matrix.nrows() as u32;
+    let mut route: Vec<u32> = Vec::new();
+
+    loop
+    {
+        let mut changed = false;
+
+        loop
+        {
+            let length = vehicle_routing::route_length(&route, adjacency_matrix, depot);
+            let mut best_insertion = None;
+            let mut best_ratio = 0.0;
+
+            for vertex in (1..=vertex_count).filter(|vertex| *vertex != depot && !route.contains(vertex))
+            {
+                let prize = prizes[vertex as usize - 1];
+                let (position, added_length) = cheapest_insertion(&route, vertex, adjacency_matrix, depot);
+
+                if prize <= 0.0 || length + added_length > budget
+                {
+                    continue;
+                }
+
+                let ratio = prize / added_length.max(local_search::EPSILON);
+
+                if ratio > best_ratio
+                {
+                    best_ratio = ratio;
+                    best_insertion = Some((position, vertex));
+                }
+            }
+
+            match best_insertion {
+                Some((position, vertex)) => {
+                    route.insert(position, vertex);
+                    changed = true;
+                }
+                None => break,
+            }
+        }
+
+        // A shorter tour leaves room for more vertices
+        vehicle_routing::improve_route(&mut route, adjacency_matrix, depot, directed);
+
+        let mut best_swap = None;
+        let mut best_gain = local_search::EPSILON;
+
+        for i in 0..route.len()
+        {
+            for vertex in (1..=vertex_count).filter(|vertex| *vertex != depot && !route.contains(vertex))
+            {
+                let gain = prizes[vertex as usize - 1] - prizes[route[i] as usize - 1];
+
+                if gain <= best_gain
+                {
+                    continue;
+                }
+
+                let mut new_route = route.clone();
+                new_route.remove(i);
+
+                let (position, _) = cheapest_insertion(&new_route, vertex, adjacency_matrix, depot);
+                new_route.insert(position, vertex);
+
+                if vehicle_routing::route_length(&new_route, adjacency_matrix, depot) <= budget
+                {
+                    best_gain = gain;
+                    best_swap = Some(new_route);
+                }
+            }
+        }
+
+        if let Some(new_route) = best_swap
+        {
+            route = new_route;
+            changed = true;
+        }
+
+        if changed == false
+        {
+            break;
+        }
+    }
+
+    return route;
+}
+
+// Starts once with every vertex and once with no vertex besides the depot, then keeps dropping the vertices that cost more length
+// than their penalty and adding the vertices whose penalty exceeds the length they add, the cheaper of both tours is kept
+fn prize_collecting(
+    adjacency_matrix: &DMatrix<f32>,
+    prizes: &Vec<f32>,
+    depot: u32,
+    directed: bool,
+) -> Vec<u32> {
+    let vertex_count = adjacency_matrix.nrows() as u32;
+    let all_vertices: Vec<u32> = (1..=vertex_count).filter(|vertex| *vertex != depot).collect();
+    let total_prize = prize_sum(&all_vertices, prizes);
+
+    let cost = |route: &Vec<u32>| vehicle_routing::route_length(route, adjacency_matrix, depot) + total_prize - prize_sum(route, prizes);
+
+    let mut best_route = Vec::new();
+    let mut best_cost = f32::INFINITY;
+
+    for mut route in [all_vertices.clone(), Vec::new()]
+    {
+        vehicle_routing::improve_route(&mut route, adjacency_matrix, depot, directed);
+
+        loop
+        {
+            let length = vehicle_routing::route_length(&route, adjacency_matrix, depot);
+            let mut best_move = None;
+            let mut best_gain = local_search::EPSILON;
+
+            for i in 0..route.len()
+            {
+                let mut new_route = route.clone();
+                let vertex = new_route.remove(i);
+                let gain = length - vehicle_routing::route_length(&new_route, adjacency_matrix, depot) - prizes[vertex as usize - 1];
+
+                if gain > best_gain
+                {
+                    best_gain = gain;
+                    best_move = Some(new_route);
+                }
+            }
+
+            for vertex in all_vertices.iter().copied().filter(|vertex| !route.contains(vertex))
+            {
+                let (position, added_length) = cheapest_insertion(&route, vertex, adjacency_matrix, depot);
+                let gain = prizes[vertex as usize - 1] - added_length;
+
+                if gain > best_gain
+                {
+                    let mut new_route = route.clone();
+                    new_route.insert(position, vertex);
+
+                    best_gain = gain;
+                    best_move = Some(new_route);
+                }
+            }
+
+            match best_move {
+                Some(new_route) => {
+                    route = new_route;
+                    vehicle_routing::improve_route(&mut route, adjacency_matrix, depot, directed);
+                }
+                None => break,
+            }
+        }
+
+        if cost(&route) < best_cost
+        {
+            best_cost = cost(&route);
+            best_route = route;
+        }
+    }
+
+    return best_route;
+}
+
+#[derive(Component)]
+struct PrizeLabel;
+
+// This system draws the prize of every vertex and reports the collected prizes and penalties of the selective routing solution
+pub fn draw_selective_routing(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    window: Query<&mut Window>,
+    vertex_list: Res<graph::VertexList>,
+    shortest_cycle: Res<graph::ShortestCycle>,
+    selective_routing: Res<SelectiveRouting>,
+    mut ant_colony_stats_text: Query<&mut Text, With<app::AntColonyStatsText>>,
+) {
+    let win = window.single();
+
+    commands.despawn_all::<With<PrizeLabel>>();
+
+    // Prizes are only shown once they matter, either a selective routing solution exists or some vertex has a prize other than the default
+    if selective_routing.solved == true || vertex_list.prizes.iter().any(|prize| *prize != graph::DEFAULT_PRIZE)
+    {
+        for (vertex, y, x) in &vertex_list.vector
+        {
+            commands.spawn((
+                TextBundle::from_section(
+                    format!("${}", vertex_list.prizes[*vertex as usize - 1]),
+                    TextStyle {
+                        font: asset_server.load("fonts/FiraSans-ExtraLight.ttf"),
+                        font_size: 20.0,
+                        color: Color::GOLD,
+                    },
+                )
+                    .with_text_alignment(TextAlignment::Left)
+                    .with_style(Style {
+                        position_type: PositionType::Absolute,
+                        position: UiRect {
+                            bottom: Val::Px(y + (win.height() / 2.) - 45.),
+                            left: Val::Px(x + (win.width() / 2.) + 18.),
+                            ..default()
+                        },
+                        ..default()
+                    }),
+                PrizeLabel,
+            ));
+        }
+    }
+
+    if selective_routing.solved == true && (selective_routing.is_changed() || vertex_list.is_changed())
+    {
+        let collected_prize = prize_sum(&selective_routing.route, &vertex_list.prizes);
+        let penalties = prize_sum(&shortest_cycle.skipped, &vertex_list.prizes);
+
+        let stats = match selective_routing.mode {
+            SelectiveRoutingMode::Orienteering => format!(
+                "Orienteering: collected prize {} of {}, tour length {:.1} of budget {}\n{} vertices skipped",
+                collected_prize,
+                collected_prize + penalties,
+                shortest_cycle.total_cycle_weight,
+                selective_routing.budget,
+                shortest_cycle.skipped.len(),
+            ),
+            SelectiveRoutingMode::PrizeCollecting => format!(
+                "Prize-collecting: tour length {:.1} + penalties {} = {:.1}\n{} vertices skipped",
+                shortest_cycle.total_cycle_weight,
+                penalties,
+                shortest_cycle.total_cycle_weight + penalties,
+                shortest_cycle.skipped.len(),
+            ),
+        };
+
+        for mut text in &mut ant_colony_stats_text
+        {
+            text.sections[0].value = stats.clone();
+        }
+    }
+}
@@ -0,0 +1,300 @@
+use crate::app;
+use crate::graph;
+use crate::local_search;
+
+use bevy::prelude::*;
+use bevy_despawn_with::DespawnAllCommandsExt;
+use bevy_prototype_debug_lines::*;
+use nalgebra::DMatrix;
+
+// This resource stores the parameters of the traveling salesman problem with time windows together with its last solution,
+// the salesman leaves the depot of the graph settings at the start of its time window and the windows are stored in the vertex list
+#[derive(Resource)]
+pub struct TimeWindows {
+    // Distance travelled per unit of time, the travel time of an edge is its weight divided by the speed
+    pub speed: f32,
+    // Vertices in the order they are visited, starting on the depot, the return to the depot is left out
+    pub tour: Vec<u32>,
+}
+
+// Arrival times at every vertex of the tour followed by the arrival back at the depot, the salesman waits when it arrives before a window opens
+pub fn schedule(
+    tour: &Vec<u32>,
+    adjacency_matrix: &DMatrix<f32>,
+    vertex_list: &graph::VertexList,
+    speed: f32,
+) -> Vec<f32> {
+    if tour.is_empty()
+    {
+        return Vec::new();
+    }
+
+    let depot = tour[0];
+    let mut arrivals = vec![vertex_list.time_windows[depot as usize - 1].0];
+    let mut time = arrivals[0] + vertex_list.service_times[depot as usize - 1];
+
+    for i in 1..=tour.len()
+    {
+        let (previous, vertex) = (tour[i - 1], tour[i % tour.len()]);
+        let arrival = time + adjacency_matrix[(previous as usize - 1, vertex as usize - 1)] / speed;
+        let (earliest, _) = vertex_list.time_windows[vertex as usize - 1];
+
+        arrivals.push(arrival);
+        time = arrival.max(earliest) + vertex_list.service_times[vertex as usize - 1];
+    }
+
+    return arrivals;
+}
+
+// Sum of the times by which the arrivals miss the closing of the time windows
+fn total_lateness(
+    tour: &Vec<u32>,
+    arrivals: &Vec<f32>,
+    vertex_list: &graph::VertexList,
+) -> f32 {
+    (0..arrivals.len())
+        .map(|i| (arrivals[i] - vertex_list.time_windows[tour[i % tour.len()] as usize - 1].1).max(0.0))
+        .sum()
+}
+
+// Tours are compared by their total lateness first and by their length second
+fn tour_cost(
+    tour: &Vec<u32>,
+    adjacency_matrix: &DMatrix<f32>,
+    vertex_list: &graph::VertexList,
+    speed: f32,
+) -> (f32, f32) {
+    let arrivals = schedule(tour, adjacency_matrix, vertex_list, speed);
+    let length = (0..tour.len()).map(|i| adjacency_matrix[(tour[i] as usize - 1, tour[(i + 1) % tour.len()] as usize - 1)]).sum();
+
+    (total_lateness(tour, &arrivals, vertex_list), length)
+}
+
+// Searches for the shortest tour that reaches every vertex within its time window, the tours ordered by the closing and by the opening
+// of the windows and the nearest neighbour tour are improved by moving segments and reversing parts of the tour and the best one is kept,
+// when no tour meets all windows, the one with the smallest total lateness is kept
+pub fn solve_time_windows(
+    time_windows: &mut TimeWindows,
+    adjacency_matrix: &DMatrix<f32>,
+    vertex_list: &graph::VertexList,
+    graph_settings: &graph::GraphSettings,
+    shortest_cycle: &mut graph::ShortestCycle,
+) {
+    shortest_cycle.vector = Vec::new();
+    shortest_cycle.total_cycle_weight = 0.0;
+    shortest_cycle.routes = Vec::new();
+    shortest_cycle.skipped = Vec::new();
+    time_windows.tour = Vec::new();
+
+    if vertex_list.count < 2 || graph_settings.depot < 1 || graph_settings.depot > vertex_list.count
+    {
+        println!("time windows: the graph needs a depot and at least one other vertex");
+        return;
+    }
+
+    let depot = graph_settings.depot;
+    let speed = time_windows.speed;
+    let mut customers: Vec<u32> = (1..=vertex_list.count).filter(|vertex| *vertex != depot).collect();
+    let mut initial_tours = Vec::new();
+
+    customers.sort_by(|a, b| vertex_list.time_windows[*a as usize - 1].1.total_cmp(&vertex_list.time_windows[*b as usize - 1].1));
+    initial_tours.push(std::iter::once(depot).chain(customers.iter().copied()).collect::<Vec<u32>>());
+
+    customers.sort_by(|a, b| vertex_list.time_windows[*a as usize - 1].0.total_cmp(&vertex_list.time_windows[*b as usize - 1].0));
+    initial_tours.push(std::iter::once(depot).chain(customers.iter().copied()).collect::<Vec<u32>>());
+
+    initial_tours.push(nearest_neighbour_tour(adjacency_matrix, depot));
+
+    let mut best_tour = Vec::new();
+    let mut best_cost = (f32::INFINITY, f32::INFINITY);
+
+    for mut tour in initial_tours
+    {
+        // Every move is checked against the whole schedule, because a single late arrival delays all of the following vertices
+        local_search::improve_constrained_tour(&mut tour, adjacency_matrix, |tour| tour_cost(tour, adjacency_matrix, vertex_list, speed));
+
+        let cost = tour_cost(&tour, adjacency_matrix, vertex_list, speed);
+
+        if best_tour.is_empty() || local_search::is_better_constrained(cost, best_cost)
+        {
+            best_tour = tour;
+            best_cost = cost;
+        }
+    }
+
+    for i in 0..best_tour.len()
+    {
+        shortest_cycle.vector.push((best_tour[i], best_tour[(i + 1) % best_tour.len()]));
+    }
+
+    shortest_cycle.routes.push((best_tour.len(), best_cost.1));
+    shortest_cycle.total_cycle_weight = best_cost.1;
+
+    if best_cost.0 > local_search::EPSILON
+    {
+        println!("time windows: no feasible tour found, total lateness {} with tour length {}", best_cost.0, best_cost.1);
+    } else {
+        println!("time windows: feasible tour {:?} with length {}", best_tour, best_cost.1);
+    }
+
+    time_windows.tour = best_tour;
+}
+
+fn nearest_neighbour_tour(
+    adjacency_matrix: &DMatrix<f32>,
+    depot: u32,
+) -> Vec<u32> {
+    let vertex_count = adjacency_matrix.nrows() as u32;
+    let mut tour = vec![depot];
+    let mut unvisited: Vec<u32> = (1..=vertex_count).filter(|vertex| *vertex != depot).collect();
+
+    while unvisited.len() > 0
+    {
+        let last = tour[tour.len() - 1] as usize - 1;
+        let nearest = (0..unvisited.len())
+            .min_by(|a, b| adjacency_matrix[(last, unvisited[*a] as usize - 1)].total_cmp(&adjacency_matrix[(last, unvisited[*b] as usize - 1)]))
+            .unwrap();
+
+        tour.push(unvisited.swap_remove(nearest));
+    }
+
+    return tour;
+}
+
+#[derive(Component)]
+struct TimeWindowLabel;
+
+// This system draws the time window of every vertex together with the arrival time of the tour, the edges leading to late arrivals
+// are drawn in red and the stats in the top left corner report the total lateness
+pub fn draw_time_windows(
+    mut commands: Commands,
+    mut lines: ResMut<DebugLines>,
+    asset_server: Res<AssetServer>,
+    window: Query<&mut Window>,
+    adjacency_matrix: Res<graph::AdjacencyMatrix>,
+    vertex_list: Res<graph::VertexList>,
+    graph_settings: Res<graph::GraphSettings>,
+    time_windows: Res<TimeWindows>,
+    mut ant_colony_stats_text: Query<&mut Text, With<app::AntColonyStatsText>>,
+) {
+    let win = window.single();
+
+    commands.despawn_all::<With<TimeWindowLabel>>();
+
+    let tour = &time_windows.tour;
+    let arrivals = schedule(tour, &adjacency_matrix.matrix, &vertex_list, time_windows.speed);
+
+    // Arrival time of every vertex, the depot is labelled with the return
+    let mut arrival_of = vec![None; vertex_list.vector.len()];
+
+    for i in 1..arrivals.len()
+    {
+        arrival_of[tour[i % tour.len()] as usize - 1] = Some(arrivals[i]);
+    }
+
+    let windows_set = vertex_list.time_windows.iter().any(|(earliest, latest)| *earliest > 0.0 || latest.is_finite())
+        || vertex_list.service_times.iter().any(|service_time| *service_time > 0.0);
+
+    if windows_set == true || tour.len() > 0
+    {
+        for (vertex, y, x) in &vertex_list.vector
+        {
+            let (earliest, latest) = vertex_list.time_windows[*vertex as usize - 1];
+            let service_time = vertex_list.service_times[*vertex as usize - 1];
+
+            let mut label = if latest.is_finite() {
+                format!("[{}, {}]", earliest, latest)
+            } else {
+                format!("[{}, -]", earliest)
+            };
+
+            if service_time > 0.0
+            {
+                label += &format!(" +{}", service_time);
+            }
+
+            let mut color = Color::rgb(0.3, 0.8, 1.0);
+
+            if let Some(arrival) = arrival_of[*vertex as usize - 1]
+            {
+                label += &format!(" @ {:.1}", arrival);
+
+                if arrival > latest + local_search::EPSILON
+                {
+                    color = Color::RED;
+                }
+            }
+
+            commands.spawn((
+                TextBundle::from_section(
+                    label,
+                    TextStyle {
+                        font: asset_server.load("fonts/FiraSans-ExtraLight.ttf"),
+                        font_size: 20.0,
+                        color,
+                    },
+                )
+                    .with_text_alignment(TextAlignment::Left)
+                    .with_style(Style {
+                        position_type: PositionType::Absolute,
+                        position: UiRect {
+                            bottom: Val::Px(y + (win.height() / 2.) + 18.),
+                            left: Val::Px(x + (win.width() / 2.) - 20.),
+                            ..default()
+                        },
+                        ..default()
+                    }),
+                TimeWindowLabel,
+            ));
+        }
+    }
+
+    let mut late_arrivals = 0;
+
+    for i in 1..arrivals.len()
+    {
+        let (previous, vertex) = (tour[i - 1], tour[i % tour.len()]);
+        let (_, y1, x1) = vertex_list.vector[previous as usize - 1];
+        let (_, y2, x2) = vertex_list.vector[vertex as usize - 1];
+
+        // The direction matters even in undirected graphs, because the windows have to be met one after another
+        if graph_settings.directed == false
+        {
+            graph::draw_arrow_head(&mut lines, Vec3::new(x1, y1, 0.), Vec3::new(x2, y2, 0.), Color::WHITE);
+        }
+
+        if arrivals[i] > vertex_list.time_windows[vertex as usize - 1].1 + local_search::EPSILON
+        {
+            graph::draw_thick_line(&mut lines, Vec3::new(x1, y1, 0.), Vec3::new(x2, y2, 0.), Color::RED, 3);
+            late_arrivals += 1;
+        }
+    }
+
+    // The schedule changes together with the windows and the service times, so the text is refreshed whenever one of them changes
+    if tour.len() > 0 && (time_windows.is_changed() || vertex_list.is_changed() || adjacency_matrix.is_changed())
+    {
+        let lateness = total_lateness(tour, &arrivals, &vertex_list);
+        let length: f32 = (0..tour.len()).map(|i| adjacency_matrix.matrix[(tour[i] as usize - 1, tour[(i + 1) % tour.len()] as usize - 1)]).sum();
+
+        let stats = if late_arrivals > 0 {
+            format!(
+                "Time windows: no feasible tour found, total lateness {:.1} at {} stops\ntour length {:.1}, back at the depot at {:.1}",
+                lateness,
+                late_arrivals,
+                length,
+                arrivals[arrivals.len() - 1],
+            )
+        } else {
+            format!(
+                "Time windows: feasible tour length {:.1}, back at the depot at {:.1}",
+                length,
+                arrivals[arrivals.len() - 1],
+            )
+        };
+
+        for mut text in &mut ant_colony_stats_text
+        {
+            text.sections[0].value = stats.clone();
+        }
+    }
+}
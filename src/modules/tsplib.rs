@@ -0,0 +1,828 @@
+use crate::app;
+use crate::graph;
+
+use bevy::prelude::*;
+use bevy_prototype_debug_lines::*;
+use nalgebra::DMatrix;
+
+// Space in pixels kept free between a loaded instance and the border of the canvas
+const CANVAS_MARGIN: f32 = 100.;
+
+// Half of the edge length of the cube the points of 3D instances are scaled into
+const SPACE_EXTENT: f32 = 400.;
+
+// Radius of the earth and the value of pi exactly as the GEO distance of TSPLIB defines them
+const GEO_EARTH_RADIUS: f64 = 6378.388;
+const GEO_PI: f64 = 3.141592;
+
+// Colour of the loaded optimal tour, it differs from every route colour
+const OPTIMAL_TOUR_COLOR: Color = Color::FUCHSIA;
+
+// How the weights of an instance are given, the coordinate types compute them with the distance functions of TSPLIB
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum EdgeWeightType {
+    Explicit,
+    Euclidean2D,
+    Euclidean3D,
+    Ceiling2D,
+    Manhattan2D,
+    // Pseudo-Euclidean distance of the att48 and att532 instances
+    Att,
+    // Coordinates are latitude and longitude in degrees and minutes, the distance is in kilometres
+    Geographical,
+}
+
+// Layout of the numbers in an explicit EDGE_WEIGHT_SECTION
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum EdgeWeightFormat {
+    FullMatrix,
+    UpperRow,
+    LowerRow,
+    UpperDiagRow,
+    LowerDiagRow,
+    UpperCol,
+    LowerCol,
+    UpperDiagCol,
+    LowerDiagCol,
+}
+
+// Section whose data lines are being read
+#[derive(Clone, Copy, PartialEq)]
+enum Section {
+    None,
+    NodeCoords,
+    DisplayData,
+    EdgeWeights,
+    // Sections of other problems, like the demands of CVRP files, are read over
+    Ignored,
+}
+
+// Instance read from a TSPLIB file, the weights come from the original coordinates, which are only scaled when the instance is drawn
+pub struct TsplibInstance {
+    pub name: String,
+    // ATSP files give a separate weight for each direction of an edge
+    pub directed: bool,
+    // Position of every node to draw it at, from its coordinates or the display data of explicit instances, empty when neither is given
+    pub coordinates: Vec<[f64; 3]>,
+    pub three_dimensional: bool,
+    // Weight of the edge leading from the row vertex to the column vertex, the diagonal is 0
+    pub weights: DMatrix<f32>,
+}
+
+// Reads and parses a .tsp or .atsp file
+pub fn read_instance(
+    path: &str,
+) -> Result<TsplibInstance, String> {
+    let text = std::fs::read_to_string(path).map_err(|error| format!("can not read {}: {}", path, error))?;
+
+    parse_instance(&text)
+}
+
+// Parses the specification part and the data sections of a TSPLIB file, errors name the line they were found on
+pub fn parse_instance(
+    text: &str,
+) -> Result<TsplibInstance, String> {
+    let mut name = String::new();
+    let mut directed = false;
+    let mut dimension: Option<usize> = None;
+    let mut edge_weight_type: Option<EdgeWeightType> = None;
+    let mut edge_weight_format: Option<(EdgeWeightFormat, String)> = None;
+
+    let mut section = Section::None;
+    let mut node_coords: Vec<Option<[f64; 3]>> = Vec::new();
+    let mut display_data: Vec<Option<[f64; 3]>> = Vec::new();
+    // Explicit weights may wrap over any number of lines, so every number keeps the line it was read from
+    let mut explicit_weights: Vec<(f64, usize)> = Vec::new();
+    let mut last_line = 0;
+
+    for (index, line) in text.lines().enumerate()
+    {
+        let line_number = index + 1;
+        let line = line.trim();
+        last_line = line_number;
+
+        if line.is_empty()
+        {
+            continue;
+        }
+
+        // Keywords start with a letter, data lines with a number or a sign
+        if line.starts_with(|character: char| character.is_ascii_alphabetic())
+        {
+            let (keyword, value) = match line.split_once(':') {
+                Some((keyword, value)) => (keyword.trim(), value.trim()),
+                None => (line, ""),
+            };
+
+            section = Section::None;
+
+            match keyword {
+                "NAME" => name = value.to_string(),
+                "TYPE" => {
+                    directed = match value {
+                        "TSP" => false,
+                        "ATSP" => true,
+                        _ => return Err(format!("line {}: type {} is not supported, only TSP and ATSP instances can be loaded", line_number, value)),
+                    };
+                }
+                "DIMENSION" => {
+                    let count: usize = value.parse().map_err(|_| format!("line {}: dimension {} is not a number", line_number, value))?;
+
+                    if count < 1
+                    {
+                        return Err(format!("line {}: the dimension has to be at least 1", line_number));
+                    }
+
+                    dimension = Some(count);
+                }
+                "EDGE_WEIGHT_TYPE" => {
+                    edge_weight_type = Some(match value {
+                        "EXPLICIT" => EdgeWeightType::Explicit,
+                        "EUC_2D" => EdgeWeightType::Euclidean2D,
+                        "EUC_3D" => EdgeWeightType::Euclidean3D,
+                        "CEIL_2D" => EdgeWeightType::Ceiling2D,
+                        "MAN_2D" => EdgeWeightType::Manhattan2D,
+                        "ATT" => EdgeWeightType::Att,
+                        "GEO" => EdgeWeightType::Geographical,
+                        _ => return Err(format!("line {}: edge weight type {} is not supported", line_number, value)),
+                    });
+                }
+                "EDGE_WEIGHT_FORMAT" => {
+                    edge_weight_format = Some(match value {
+                        "FULL_MATRIX" => EdgeWeightFormat::FullMatrix,
+                        "UPPER_ROW" => EdgeWeightFormat::UpperRow,
+                        "LOWER_ROW" => EdgeWeightFormat::LowerRow,
+                        "UPPER_DIAG_ROW" => EdgeWeightFormat::UpperDiagRow,
+                        "LOWER_DIAG_ROW" => EdgeWeightFormat::LowerDiagRow,
+                        "UPPER_COL" => EdgeWeightFormat::UpperCol,
+                        "LOWER_COL" => EdgeWeightFormat::LowerCol,
+                        "UPPER_DIAG_COL" => EdgeWeightFormat::UpperDiagCol,
+                        "LOWER_DIAG_COL" => EdgeWeightFormat::LowerDiagCol,
+                        _ => return Err(format!("line {}: edge weight format {} is not supported", line_number, value)),
+                    }).map(|format| (format, value.to_string()));
+                }
+                "NODE_COORD_SECTION" | "DISPLAY_DATA_SECTION" | "EDGE_WEIGHT_SECTION" => {
+                    let count = dimension.ok_or(format!("line {}: DIMENSION has to be given before {}", line_number, keyword))?;
+
+                    section = match keyword {
+                        "NODE_COORD_SECTION" => Section::NodeCoords,
+                        "DISPLAY_DATA_SECTION" => Section::DisplayData,
+                        _ => Section::EdgeWeights,
+                    };
+
+                    node_coords.resize(count, None);
+                    display_data.resize(count, None);
+                }
+                "EOF" => break,
+                _ if keyword.ends_with("_SECTION") => section = Section::Ignored,
+                // Comments and the keywords of other problems do not change the instance
+                _ => {}
+            }
+
+            continue;
+        }
+
+        match section {
+            Section::None => return Err(format!("line {}: data outside of a section", line_number)),
+            Section::Ignored => {}
+            Section::EdgeWeights => {
+                for token in line.split_whitespace()
+                {
+                    let weight: f64 = token.parse().map_err(|_| format!("line {}: weight {} is not a number", line_number, token))?;
+                    explicit_weights.push((weight, line_number));
+                }
+            }
+            Section::NodeCoords | Section::DisplayData => {
+                let dimensions = if section == Section::NodeCoords && edge_weight_type == Some(EdgeWeightType::Euclidean3D) { 3 } else { 2 };
+                let tokens: Vec<&str> = line.split_whitespace().collect();
+
+                if tokens.len() != dimensions + 1
+                {
+                    return Err(format!("line {}: expected a node number and {} coordinates, found {} values", line_number, dimensions, tokens.len()));
+                }
+
+                let node: usize = tokens[0].parse().map_err(|_| format!("line {}: node number {} is not a whole number", line_number, tokens[0]))?;
+                let mut coordinates = [0.0; 3];
+
+                for i in 0..dimensions
+                {
+                    coordinates[i] = tokens[i + 1].parse().map_err(|_| format!("line {}: coordinate {} is not a number", line_number, tokens[i + 1]))?;
+                }
+
+                let nodes = if section == Section::NodeCoords { &mut node_coords } else { &mut display_data };
+
+                if node < 1 || node > nodes.len()
+                {
+                    return Err(format!("line {}: node {} is outside of the dimension {}", line_number, node, nodes.len()));
+                }
+
+                if nodes[node - 1].is_some()
+                {
+                    return Err(format!("line {}: node {} is given twice", line_number, node));
+                }
+
+                nodes[node - 1] = Some(coordinates);
+            }
+        }
+    }
+
+    let count = dimension.ok_or(format!("line {}: the file ends without a DIMENSION", last_line))?;
+    let edge_weight_type = edge_weight_type.ok_or(format!("line {}: the file ends without an EDGE_WEIGHT_TYPE", last_line))?;
+
+    // Every node of the section needs its coordinates, a missing section leaves all of them empty
+    let complete = |nodes: &Vec<Option<[f64; 3]>>, section_name: &str| -> Result<Vec<[f64; 3]>, String> {
+        match nodes.iter().position(|coordinates| coordinates.is_none()) {
+            Some(missing) => Err(format!("line {}: node {} has no coordinates in the {}", last_line, missing + 1, section_name)),
+            None => Ok(nodes.iter().flatten().copied().collect()),
+        }
+    };
+
+    let (coordinates, weights) = if edge_weight_type == EdgeWeightType::Explicit {
+        let (format, format_name) = edge_weight_format.ok_or(format!("line {}: explicit weights need an EDGE_WEIGHT_FORMAT", last_line))?;
+        let weights = explicit_matrix(&explicit_weights, format, &format_name, count, last_line)?;
+        let coordinates = if display_data.iter().any(|coordinates| coordinates.is_some()) { complete(&display_data, "DISPLAY_DATA_SECTION")? } else { Vec::new() };
+
+        (coordinates, weights)
+    } else {
+        if node_coords.is_empty()
+        {
+            return Err(format!("line {}: the file ends without a NODE_COORD_SECTION", last_line));
+        }
+
+        let coordinates = complete(&node_coords, "NODE_COORD_SECTION")?;
+        let weights = DMatrix::from_fn(count, count, |i, j| {
+            if i == j { 0.0 } else { coordinate_distance(coordinates[i], coordinates[j], edge_weight_type) as f32 }
+        });
+
+        // North is up on the canvas, so the longitude of GEO instances is drawn along the width and the latitude along the height
+        let coordinates = if edge_weight_type == EdgeWeightType::Geographical {
+            coordinates.iter().map(|[latitude, longitude, _]| [*longitude, *latitude, 0.0]).collect()
+        } else {
+            coordinates
+        };
+
+        (coordinates, weights)
+    };
+
+    Ok(TsplibInstance {
+        name: name,
+        directed: directed,
+        coordinates: coordinates,
+        three_dimensional: edge_weight_type == EdgeWeightType::Euclidean3D,
+        weights: weights,
+    })
+}
+
+// Rounds to the nearest integer like the nint function of TSPLIB
+fn nint(
+    value: f64,
+) -> f64 {
+    (value + 0.5).floor()
+}
+
+// Degrees and minutes of a GEO coordinate in radians, 12.30 stands for 12 degrees and 30 minutes
+fn geo_radians(
+    coordinate: f64,
+) -> f64 {
+    let degrees = coordinate.trunc();
+    let minutes = coordinate - degrees;
+
+    GEO_PI * (degrees + 5.0 * minutes / 3.0) / 180.0
+}
+
+// Distance between two nodes by the distance function of the edge weight type
+fn coordinate_distance(
+    point1: [f64; 3],
+    point2: [f64; 3],
+    edge_weight_type: EdgeWeightType,
+) -> f64 {
+    let dx = point1[0] - point2[0];
+    let dy = point1[1] - point2[1];
+    let dz = point1[2] - point2[2];
+
+    match edge_weight_type {
+        EdgeWeightType::Euclidean2D => nint((dx * dx + dy * dy).sqrt()),
+        EdgeWeightType::Euclidean3D => nint((dx * dx + dy * dy + dz * dz).sqrt()),
+        EdgeWeightType::Ceiling2D => (dx * dx + dy * dy).sqrt().ceil(),
+        EdgeWeightType::Manhattan2D => nint(dx.abs() + dy.abs()),
+        EdgeWeightType::Att => {
+            let distance = ((dx * dx + dy * dy) / 10.0).sqrt();
+            let rounded = nint(distance);
+
+            if rounded < distance { rounded + 1.0 } else { rounded }
+        }
+        EdgeWeightType::Geographical => {
+            // The first coordinate is the latitude, the second one the longitude
+            let (latitude1, longitude1) = (geo_radians(point1[0]), geo_radians(point1[1]));
+            let (latitude2, longitude2) = (geo_radians(point2[0]), geo_radians(point2[1]));
+
+            let q1 = (longitude1 - longitude2).cos();
+            let q2 = (latitude1 - latitude2).cos();
+            let q3 = (latitude1 + latitude2).cos();
+
+            (GEO_EARTH_RADIUS * (0.5 * ((1.0 + q1) * q2 - (1.0 - q1) * q3)).acos() + 1.0).trunc()
+        }
+        EdgeWeightType::Explicit => 0.0,
+    }
+}
+
+// Fills the weight matrix from the numbers of an explicit EDGE_WEIGHT_SECTION, the triangular formats describe symmetric instances
+// and a triangle read by columns holds the numbers in the same order as the opposite triangle read by rows
+fn explicit_matrix(
+    explicit_weights: &Vec<(f64, usize)>,
+    format: EdgeWeightFormat,
+    format_name: &str,
+    count: usize,
+    last_line: usize,
+) -> Result<DMatrix<f32>, String> {
+    let row_format = match format {
+        EdgeWeightFormat::UpperCol => EdgeWeightFormat::LowerRow,
+        EdgeWeightFormat::LowerCol => EdgeWeightFormat::UpperRow,
+        EdgeWeightFormat::UpperDiagCol => EdgeWeightFormat::LowerDiagRow,
+        EdgeWeightFormat::LowerDiagCol => EdgeWeightFormat::UpperDiagRow,
+        _ => format,
+    };
+
+    // Cells of the matrix in the order their numbers appear in the file
+    let cells: Vec<(usize, usize)> = (0..count)
+        .flat_map(|i| {
+            let columns = match row_format {
+                EdgeWeightFormat::UpperRow => i + 1..count,
+                EdgeWeightFormat::LowerRow => 0..i,
+                EdgeWeightFormat::UpperDiagRow => i..count,
+                EdgeWeightFormat::LowerDiagRow => 0..i + 1,
+                _ => 0..count,
+            };
+
+            columns.map(move |j| (i, j))
+        })
+        .collect();
+
+    if explicit_weights.len() < cells.len()
+    {
+        return Err(format!("line {}: the EDGE_WEIGHT_SECTION ends after {} of the {} weights of a {} of dimension {}",
+            explicit_weights.last().map(|(_, line)| *line).unwrap_or(last_line), explicit_weights.len(), cells.len(), format_name, count));
+    }
+
+    if explicit_weights.len() > cells.len()
+    {
+        return Err(format!("line {}: the EDGE_WEIGHT_SECTION has more than the {} weights of a {} of dimension {}",
+            explicit_weights[cells.len()].1, cells.len(), format_name, count));
+    }
+
+    let mut weights = DMatrix::from_diagonal_element(count, count, 0.0);
+
+    for ((i, j), (weight, _)) in cells.into_iter().zip(explicit_weights)
+    {
+        // The diagonal of ATSP files often holds a large number to forbid loops, loops are never part of a tour anyway
+        if i == j
+        {
+            continue;
+        }
+
+        weights[(i, j)] = *weight as f32;
+
+        if format != EdgeWeightFormat::FullMatrix
+        {
+            weights[(j, i)] = *weight as f32;
+        }
+    }
+
+    Ok(weights)
+}
+
+// Replaces the vertices and weights of the graph by the instance, which has to be cleared beforehand, the coordinates are scaled and centred
+// to fit the canvas, while the weights stay those of the file, explicit instances without display data are placed on a circle
+pub fn load_instance(
+    instance: TsplibInstance,
+    vertex_list: &mut graph::VertexList,
+    edge_list: &mut graph::EdgeList,
+    adjacency_matrix: &mut graph::AdjacencyMatrix,
+    graph_settings: &mut graph::GraphSettings,
+) {
+    let count = instance.weights.nrows();
+    let positions = canvas_positions(&instance, graph_settings);
+
+    for position in positions
+    {
+        // The canvas position of 3D instances is filled in by project_vertices
+        vertex_list.push_vertex(position.y, position.x, if instance.three_dimensional == true { Some(position) } else { None });
+    }
+
+    graph_settings.directed = instance.directed;
+    edge_list.fixed_weights = Some(instance.weights);
+
+    graph::rebuild_graph(vertex_list, edge_list, adjacency_matrix, graph_settings);
+
+    println!("loaded {} with {} vertices", if instance.name.is_empty() { "instance" } else { &instance.name }, count);
+}
+
+// Position of every vertex on the canvas, or in space for 3D instances
+fn canvas_positions(
+    instance: &TsplibInstance,
+    graph_settings: &graph::GraphSettings,
+) -> Vec<Vec3> {
+    let count = instance.weights.nrows();
+
+    if instance.coordinates.is_empty()
+    {
+        let radius = (graph_settings.canvas_width.min(graph_settings.canvas_height) / 2. - CANVAS_MARGIN).max(0.);
+
+        // Clockwise from the top of the circle
+        return (0..count)
+            .map(|i| {
+                let angle = std::f32::consts::TAU * i as f32 / count as f32;
+                Vec3::new(radius * angle.sin(), radius * angle.cos(), 0.)
+            })
+            .collect();
+    }
+
+    let points: Vec<Vec3> = instance.coordinates.iter().map(|[x, y, z]| Vec3::new(*x as f32, *y as f32, *z as f32)).collect();
+
+    let minimum = points.iter().fold(Vec3::splat(f32::INFINITY), |minimum, point| minimum.min(*point));
+    let maximum = points.iter().fold(Vec3::splat(f32::NEG_INFINITY), |maximum, point| maximum.max(*point));
+    let centre = (minimum + maximum) / 2.;
+    let size = maximum - minimum;
+
+    let scale = if instance.three_dimensional == true {
+        2. * SPACE_EXTENT / size.max_element()
+    } else {
+        ((graph_settings.canvas_width - 2. * CANVAS_MARGIN) / size.x).min((graph_settings.canvas_height - 2. * CANVAS_MARGIN) / size.y)
+    };
+
+    // All nodes on a single spot or line leave the scale infinite in that direction
+    let scale = if scale.is_finite() { scale } else { 1. };
+
+    points.iter().map(|point| (*point - centre) * scale).collect()
+}
+
+// This resource stores the optimal tour loaded for the current instance, it is drawn beside the tour of the solvers together with the gap between them
+#[derive(Resource)]
+pub struct OptimalTour {
+    pub tour: Vec<u32>,
+    pub name: String,
+}
+
+// Reads and parses a .tour or .opt.tour file, returns the name of the tour and its vertices
+pub fn read_tour(
+    path: &str,
+) -> Result<(String, Vec<u32>), String> {
+    let text = std::fs::read_to_string(path).map_err(|error| format!("can not read {}: {}", path, error))?;
+
+    parse_tour(&text)
+}
+
+// Parses a TSPLIB tour, the TOUR_SECTION lists every node once and ends with -1
+pub fn parse_tour(
+    text: &str,
+) -> Result<(String, Vec<u32>), String> {
+    let mut name = String::new();
+    let mut dimension: Option<usize> = None;
+    let mut tour: Vec<u32> = Vec::new();
+    let mut in_tour_section = false;
+    let mut tour_ended = false;
+    let mut last_line = 0;
+
+    for (index, line) in text.lines().enumerate()
+    {
+        let line_number = index + 1;
+        let line = line.trim();
+        last_line = line_number;
+
+        if line.is_empty()
+        {
+            continue;
+        }
+
+        if line.starts_with(|character: char| character.is_ascii_alphabetic())
+        {
+            let (keyword, value) = match line.split_once(':') {
+                Some((keyword, value)) => (keyword.trim(), value.trim()),
+                None => (line, ""),
+            };
+
+            in_tour_section = false;
+
+            match keyword {
+                "NAME" => name = value.to_string(),
+                "TYPE" if value != "TOUR" => return Err(format!("line {}: type {} is not a tour", line_number, value)),
+                "DIMENSION" => dimension = Some(value.parse().map_err(|_| format!("line {}: dimension {} is not a number", line_number, value))?),
+                "TOUR_SECTION" => in_tour_section = true,
+                "EOF" => break,
+                _ => {}
+            }
+
+            continue;
+        }
+
+        if in_tour_section == false || tour_ended == true
+        {
+            return Err(format!("line {}: data outside of the TOUR_SECTION", line_number));
+        }
+
+        for token in line.split_whitespace()
+        {
+            let node: i64 = token.parse().map_err(|_| format!("line {}: node {} is not a whole number", line_number, token))?;
+
+            if node == -1
+            {
+                tour_ended = true;
+                in_tour_section = false;
+                break;
+            }
+
+            if node < 1 || dimension.map_or(false, |count| node as usize > count)
+            {
+                return Err(format!("line {}: node {} is outside of the dimension", line_number, node));
+            }
+
+            if tour.contains(&(node as u32))
+            {
+                return Err(format!("line {}: node {} is visited twice", line_number, node));
+            }
+
+            tour.push(node as u32);
+        }
+    }
+
+    let count = dimension.unwrap_or(tour.len());
+
+    if tour.len() != count || (1..=count as u32).any(|vertex| !tour.contains(&vertex))
+    {
+        return Err(format!("line {}: the tour visits {} of the {} nodes", last_line, tour.len(), count));
+    }
+
+    Ok((name, tour))
+}
+
+// Reads the vertices of the shortest cycle in the order they are visited, only a single tour through every vertex can be written as a TSPLIB tour
+pub fn cycle_tour(
+    shortest_cycle: &graph::ShortestCycle,
+    vertex_count: u32,
+) -> Result<Vec<u32>, String> {
+    if shortest_cycle.vector.is_empty()
+    {
+        return Err("there is no tour to save".to_string());
+    }
+
+    let mut tour: Vec<u32> = std::iter::once(shortest_cycle.vector[0].0).chain(shortest_cycle.vector.iter().map(|(_, vertex)| *vertex)).collect();
+
+    // A cycle ends on its first vertex, an open path does not
+    if tour.len() > 1 && tour[0] == tour[tour.len() - 1]
+    {
+        tour.pop();
+    }
+
+    if tour.len() != vertex_count as usize || (1..=vertex_count).any(|vertex| !tour.contains(&vertex))
+    {
+        return Err("only a single tour visiting every vertex once can be saved".to_string());
+    }
+
+    Ok(tour)
+}
+
+// Writes the tour as a TSPLIB .tour file named after the file, its length is noted in the comment
+pub fn write_tour(
+    path: &str,
+    tour: &Vec<u32>,
+    tour_length: f32,
+) -> Result<(), String> {
+    let name = std::path::Path::new(path).file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or(path.to_string());
+
+    let mut text = format!("NAME : {}\nCOMMENT : Length {}\nTYPE : TOUR\nDIMENSION : {}\nTOUR_SECTION\n", name, tour_length, tour.len());
+
+    for vertex in tour
+    {
+        text += &format!("{}\n", vertex);
+    }
+
+    text += "-1\nEOF\n";
+
+    std::fs::write(path, text).map_err(|error| format!("can not write {}: {}", path, error))
+}
+
+// This system draws the loaded optimal tour slightly beside the tour of the solvers, so that both stay visible where they share edges,
+// and shows how much longer the tour of the solvers is
+pub fn draw_optimal_tour(
+    mut lines: ResMut<DebugLines>,
+    vertex_list: Res<graph::VertexList>,
+    adjacency_matrix: Res<graph::AdjacencyMatrix>,
+    shortest_cycle: Res<graph::ShortestCycle>,
+    graph_settings: Res<graph::GraphSettings>,
+    optimal_tour: Res<OptimalTour>,
+    mut optimal_tour_text: Query<&mut Text, With<app::OptimalTourText>>,
+) {
+    // Vertices added after the tour was loaded make it a tour of another instance
+    let current = optimal_tour.tour.len() > 0 && optimal_tour.tour.len() == vertex_list.count as usize;
+
+    if current == true
+    {
+        for i in 0..optimal_tour.tour.len()
+        {
+            let (_, y1, x1) = vertex_list.vector[optimal_tour.tour[i] as usize - 1];
+            let (_, y2, x2) = vertex_list.vector[optimal_tour.tour[(i + 1) % optimal_tour.tour.len()] as usize - 1];
+
+            let (start, end) = (Vec3::new(x1, y1, 0.), Vec3::new(x2, y2, 0.));
+            let offset = Vec3::new(start.y - end.y, end.x - start.x, 0.).normalize_or_zero() * 4.;
+
+            lines.line_colored(start + offset, end + offset, 0.0, OPTIMAL_TOUR_COLOR);
+
+            if graph_settings.directed == true
+            {
+                graph::draw_arrow_head(&mut lines, start + offset, end + offset, OPTIMAL_TOUR_COLOR);
+            }
+        }
+    }
+
+    if optimal_tour.is_changed() || shortest_cycle.is_changed() || adjacency_matrix.is_changed() || vertex_list.is_changed()
+    {
+        let stats = if current == true {
+            let optimal_length: f32 = (0..optimal_tour.tour.len())
+                .map(|i| adjacency_matrix.matrix[(optimal_tour.tour[i] as usize - 1, optimal_tour.tour[(i + 1) % optimal_tour.tour.len()] as usize - 1)])
+                .sum();
+
+            // Paths, several salesmen and tours skipping vertices are not comparable to a single cycle
+            let comparable = shortest_cycle.vector.len() == optimal_tour.tour.len() && shortest_cycle.routes.len() == 1 && shortest_cycle.skipped.is_empty();
+
+            if comparable == true && optimal_length > 0.0
+            {
+                format!("Optimal tour {}: length {:.1}\nGap: {:.2} %", optimal_tour.name, optimal_length,
+                    (shortest_cycle.total_cycle_weight - optimal_length) / optimal_length * 100.)
+            } else {
+                format!("Optimal tour {}: length {:.1}", optimal_tour.name, optimal_length)
+            }
+        } else {
+            String::new()
+        };
+
+        for mut text in &mut optimal_tour_text
+        {
+            text.sections[0].value = stats.clone();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Symmetric weights of four nodes, w(1, 2) = 1, w(1, 3) = 2, w(1, 4) = 3, w(2, 3) = 4, w(2, 4) = 5 and w(3, 4) = 6
+    const SYMMETRIC_WEIGHTS: [[f32; 4]; 4] = [
+        [0.0, 1.0, 2.0, 3.0],
+        [1.0, 0.0, 4.0, 5.0],
+        [2.0, 4.0, 0.0, 6.0],
+        [3.0, 5.0, 6.0, 0.0],
+    ];
+
+    // Coordinates of berlin52, whose optimal tour has the length 7542
+    const BERLIN52_COORDINATES: [(f64, f64); 52] = [
+        (565.0, 575.0), (25.0, 185.0), (345.0, 750.0), (945.0, 685.0), (845.0, 655.0), (880.0, 660.0), (25.0, 230.0), (525.0, 1000.0),
+        (580.0, 1175.0), (650.0, 1130.0), (1605.0, 620.0), (1220.0, 580.0), (1465.0, 200.0), (1530.0, 5.0), (845.0, 680.0), (725.0, 370.0),
+        (145.0, 665.0), (415.0, 635.0), (510.0, 875.0), (560.0, 365.0), (300.0, 465.0), (520.0, 585.0), (480.0, 415.0), (835.0, 625.0),
+        (975.0, 580.0), (1215.0, 245.0), (1320.0, 315.0), (1250.0, 400.0), (660.0, 180.0), (410.0, 250.0), (420.0, 555.0), (575.0, 665.0),
+        (1150.0, 1160.0), (700.0, 580.0), (685.0, 595.0), (685.0, 610.0), (770.0, 610.0), (795.0, 645.0), (720.0, 635.0), (760.0, 650.0),
+        (475.0, 960.0), (95.0, 260.0), (875.0, 920.0), (700.0, 500.0), (555.0, 815.0), (830.0, 485.0), (1170.0, 65.0), (830.0, 610.0),
+        (605.0, 625.0), (595.0, 360.0), (1340.0, 725.0), (1740.0, 245.0),
+    ];
+
+    const BERLIN52_OPTIMAL_TOUR: &str = "NAME: berlin52.opt.tour
+TYPE: TOUR
+DIMENSION: 52
+TOUR_SECTION
+1 49 32 45 19 41 8 9 10 43 33 51 11 52 14 13 47 26 27 28 12 25 4 6 15 5 24 48 38 37 40 39 36 35 34 44 46 16 29 50 20 23 30 2 7 42 21 17 3
+18 31 22
+-1
+EOF
+";
+
+    fn explicit_instance(
+        format: &str,
+        weights: &str,
+    ) -> String {
+        format!("NAME: explicit\nTYPE: TSP\nDIMENSION: 4\nEDGE_WEIGHT_TYPE: EXPLICIT\nEDGE_WEIGHT_FORMAT: {}\nEDGE_WEIGHT_SECTION\n{}\nEOF\n", format, weights)
+    }
+
+    fn coordinate_instance(
+        edge_weight_type: &str,
+        coordinates: &[&str],
+    ) -> String {
+        let nodes: Vec<String> = coordinates.iter().enumerate().map(|(i, coordinates)| format!("{} {}", i + 1, coordinates)).collect();
+
+        format!("NAME: coordinates\nTYPE: TSP\nDIMENSION: {}\nEDGE_WEIGHT_TYPE: {}\nNODE_COORD_SECTION\n{}\nEOF\n", coordinates.len(), edge_weight_type, nodes.join("\n"))
+    }
+
+    #[test]
+    fn every_explicit_format_gives_the_same_matrix() {
+        // The numbers wrap over the lines differently than the rows, which the parser has to ignore
+        let formats = [
+            ("FULL_MATRIX", "0 1 2 3 1 0\n4 5 2 4 0 6\n3 5 6 0"),
+            ("UPPER_ROW", "1 2 3\n4 5\n6"),
+            ("LOWER_ROW", "1\n2 4\n3 5 6"),
+            ("UPPER_DIAG_ROW", "0 1 2 3\n0 4 5\n0 6\n0"),
+            ("LOWER_DIAG_ROW", "0\n1 0\n2 4 0\n3 5 6 0"),
+            ("UPPER_COL", "1\n2 4\n3 5 6"),
+            ("LOWER_COL", "1 2 3\n4 5\n6"),
+            ("UPPER_DIAG_COL", "0\n1 0\n2 4 0\n3 5 6 0"),
+            ("LOWER_DIAG_COL", "0 1 2 3\n0 4 5\n0 6\n0"),
+        ];
+
+        for (format, weights) in formats
+        {
+            let instance = parse_instance(&explicit_instance(format, weights)).unwrap();
+
+            for i in 0..4
+            {
+                for j in 0..4
+                {
+                    assert_eq!(instance.weights[(i, j)], SYMMETRIC_WEIGHTS[i][j], "{} at ({}, {})", format, i, j);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn atsp_full_matrix_keeps_both_directions() {
+        let text = "NAME: asymmetric\nTYPE: ATSP\nDIMENSION: 3\nEDGE_WEIGHT_TYPE: EXPLICIT\nEDGE_WEIGHT_FORMAT: FULL_MATRIX\nEDGE_WEIGHT_SECTION\n9999 1 2\n3 9999 4\n5 6 9999\nEOF\n";
+        let instance = parse_instance(text).unwrap();
+
+        assert!(instance.directed);
+        assert_eq!(instance.weights[(0, 1)], 1.0);
+        assert_eq!(instance.weights[(1, 0)], 3.0);
+        assert_eq!(instance.weights[(2, 1)], 6.0);
+
+        // The large diagonal forbids loops in the file, the graph has none anyway
+        assert_eq!(instance.weights[(0, 0)], 0.0);
+    }
+
+    #[test]
+    fn nint_rounds_halves_up() {
+        assert_eq!(nint(2.5), 3.0);
+        assert_eq!(nint(2.49), 2.0);
+        assert_eq!(nint(0.0), 0.0);
+    }
+
+    #[test]
+    fn geo_radians_reads_degrees_and_minutes() {
+        assert!((geo_radians(12.30) - GEO_PI * 12.5 / 180.0).abs() < 1e-9);
+        assert!((geo_radians(-45.0) - GEO_PI * -45.0 / 180.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn coordinate_types_use_the_tsplib_distance_functions() {
+        let euclidean = parse_instance(&coordinate_instance("EUC_2D", &["0 0", "3 4", "1.5 2"])).unwrap();
+        assert_eq!(euclidean.weights[(0, 1)], 5.0);
+        assert_eq!(euclidean.weights[(0, 2)], 3.0);
+
+        let euclidean_3d = parse_instance(&coordinate_instance("EUC_3D", &["0 0 0", "1 2 2"])).unwrap();
+        assert!(euclidean_3d.three_dimensional);
+        assert_eq!(euclidean_3d.weights[(0, 1)], 3.0);
+
+        let ceiling = parse_instance(&coordinate_instance("CEIL_2D", &["0 0", "1 1"])).unwrap();
+        assert_eq!(ceiling.weights[(0, 1)], 2.0);
+
+        let manhattan = parse_instance(&coordinate_instance("MAN_2D", &["0 0", "3 4"])).unwrap();
+        assert_eq!(manhattan.weights[(0, 1)], 7.0);
+
+        // The first two nodes of att48
+        let att = parse_instance(&coordinate_instance("ATT", &["6734 1453", "2233 10"])).unwrap();
+        assert_eq!(att.weights[(0, 1)], 1495.0);
+
+        // The first three nodes of burma14, the first row of its distance matrix starts with 153 and 510
+        let geo = parse_instance(&coordinate_instance("GEO", &["16.47 96.10", "16.47 94.44", "20.09 92.54"])).unwrap();
+        assert_eq!(geo.weights[(0, 1)], 153.0);
+        assert_eq!(geo.weights[(0, 2)], 510.0);
+
+        // The longitude is drawn along the width
+        assert_eq!(geo.coordinates[0], [96.10, 16.47, 0.0]);
+    }
+
+    #[test]
+    fn berlin52_optimal_tour_has_the_known_length() {
+        let coordinates: Vec<String> = BERLIN52_COORDINATES.iter().map(|(x, y)| format!("{} {}", x, y)).collect();
+        let coordinates: Vec<&str> = coordinates.iter().map(|coordinates| coordinates.as_str()).collect();
+
+        let instance = parse_instance(&coordinate_instance("EUC_2D", &coordinates)).unwrap();
+        let (name, tour) = parse_tour(BERLIN52_OPTIMAL_TOUR).unwrap();
+
+        let length: f32 = (0..tour.len())
+            .map(|i| instance.weights[(tour[i] as usize - 1, tour[(i + 1) % tour.len()] as usize - 1)])
+            .sum();
+
+        assert_eq!(name, "berlin52.opt.tour");
+        assert_eq!(length, 7542.0);
+    }
+
+    #[test]
+    fn malformed_files_name_the_line_of_the_error() {
+        let coordinates = "NAME: broken\nTYPE: TSP\nDIMENSION: 3\nEDGE_WEIGHT_TYPE: EUC_2D\nNODE_COORD_SECTION\n1 0 0\n2 x 0\n3 1 1\nEOF\n";
+        let error = parse_instance(coordinates).err().unwrap();
+        assert!(error.starts_with("line 7:"), "{}", error);
+
+        // A missing weight is reported on the line of the last weight that was read
+        let error = parse_instance(&explicit_instance("UPPER_ROW", "1 2 3\n4 5")).err().unwrap();
+        assert!(error.starts_with("line 8:"), "{}", error);
+
+        let error = parse_instance(&explicit_instance("UPPER_ROW", "1 2 3\n4 5\n6 7")).err().unwrap();
+        assert!(error.starts_with("line 9:"), "{}", error);
+    }
+}
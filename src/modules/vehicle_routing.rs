@@ -0,0 +1,762 @@
+use crate::app;
+use crate::graph;
+use crate::local_search;
+
+use bevy::prelude::*;
+use bevy_despawn_with::DespawnAllCommandsExt;
+use bevy_prototype_debug_lines::*;
+use nalgebra::DMatrix;
+
+// This resource stores the parameters of the capacitated vehicle routing problem together with its last solution,
+// the vehicles start on the depot of the graph settings and the demands are stored in the vertex list
+#[derive(Resource)]
+pub struct VehicleRouting {
+    pub capacity: u32,
+    // Number of available vehicles, 0 means that as many vehicles as needed are available
+    pub vehicles: u32,
+    pub route_local_search: bool,
+    // Customers served by every vehicle in the order they are visited, the depot is left out
+    pub routes: Vec<Vec<u32>>,
+    pub depot: u32,
+    pub solver: String,
+}
+
+// Construction heuristics of the capacitated vehicle routing problem
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum VehicleRoutingSolver {
+    // Starts with one route per customer and merges the routes with the largest savings
+    ClarkeWright,
+    // Sorts the customers by their angle around the depot and fills the vehicles one after another
+    Sweep,
+}
+
+// Solves the capacitated vehicle routing problem on the current graph and stores the routes so that they are drawn in their own colours,
+// when the vehicles do not suffice for the demands, the surplus routes are merged and flagged as over capacity
+pub fn solve_vehicle_routing(
+    vehicle_routing: &mut VehicleRouting,
+    solver: VehicleRoutingSolver,
+    adjacency_matrix: &DMatrix<f32>,
+    vertex_list: &graph::VertexList,
+    graph_settings: &graph::GraphSettings,
+    shortest_cycle: &mut graph::ShortestCycle,
+) {
+    shortest_cycle.vector = Vec::new();
+    shortest_cycle.total_cycle_weight = 0.0;
+    shortest_cycle.routes = Vec::new();
+    shortest_cycle.skipped = Vec::new();
+    vehicle_routing.routes = Vec::new();
+
+    if vertex_list.count < 2 || graph_settings.depot < 1 || graph_settings.depot > vertex_list.count
+    {
+        println!("vehicle routing: the graph needs a depot and at least one customer");
+        return;
+    }
+
+    let depot = graph_settings.depot;
+    let capacity = vehicle_routing.capacity;
+    let vehicles = vehicle_routing.vehicles;
+
+    let mut routes = match solver {
+        VehicleRoutingSolver::ClarkeWright => clarke_wright(adjacency_matrix, &vertex_list.demands, depot, capacity, vehicles, graph_settings.directed),
+        VehicleRoutingSolver::Sweep => sweep(adjacency_matrix, vertex_list, depot, capacity, vehicles, graph_settings.directed),
+    };
+
+    if vehicle_routing.route_local_search == true
+    {
+        improve_routes(&mut routes, adjacency_matrix, &vertex_list.demands, depot, capacity, graph_settings.directed);
+    }
+
+    for route in &routes
+    {
+        let length = route_length(route, adjacency_matrix, depot);
+        let mut previous = depot;
+
+        for vertex in route.iter().copied().chain(std::iter::once(depot))
+        {
+            shortest_cycle.vector.push((previous, vertex));
+            previous = vertex;
+        }
+
+        shortest_cycle.routes.push((route.len() + 1, length));
+        shortest_cycle.total_cycle_weight += length;
+    }
+
+    vehicle_routing.routes = routes;
+    vehicle_routing.depot = depot;
+    vehicle_routing.solver = match solver {
+        VehicleRoutingSolver::ClarkeWright => "Clarke-Wright".to_string(),
+        VehicleRoutingSolver::Sweep => "Sweep".to_string(),
+    };
+
+    println!("{}: {} routes with total length {}", vehicle_routing.solver, vehicle_routing.routes.len(), shortest_cycle.total_cycle_weight);
+}
+
+fn distance(
+    adjacency_matrix: &DMatrix<f32>,
+    vertex1: u32,
+    vertex2: u32,
+) -> f32 {
+    adjacency_matrix[(vertex1 as usize - 1, vertex2 as usize - 1)]
+}
+
+// Length of the route including the edges leaving and entering the depot, an empty route costs nothing
+pub fn route_length(
+    route: &[u32],
+    adjacency_matrix: &DMatrix<f32>,
+    depot: u32,
+) -> f32 {
+    if route.is_empty()
+    {
+        return 0.0;
+    }
+
+    let mut length = distance(adjacency_matrix, depot, route[0]) + distance(adjacency_matrix, route[route.len() - 1], depot);
+
+    for i in 1..route.len()
+    {
+        length += distance(adjacency_matrix, route[i - 1], route[i]);
+    }
+
+    return length;
+}
+
+pub fn route_load(
+    route: &[u32],
+    demands: &Vec<u32>,
+) -> u32 {
+    route.iter().map(|vertex| demands[*vertex as usize - 1]).sum()
+}
+
+// Routes are compared by the demand exceeding the capacity first and by their length second
+fn route_cost(
+    route: &[u32],
+    adjacency_matrix: &DMatrix<f32>,
+    demands: &Vec<u32>,
+    depot: u32,
+    capacity: u32,
+) -> (u32, f32) {
+    (route_load(route, demands).saturating_sub(capacity), route_length(route, adjacency_matrix, depot))
+}
+
+fn add_costs(
+    cost1: (u32, f32),
+    cost2: (u32, f32),
+) -> (u32, f32) {
+    (cost1.0 + cost2.0, cost1.1 + cost2.1)
+}
+
+fn is_better(
+    new_cost: (u32, f32),
+    old_cost: (u32, f32),
+) -> bool {
+    new_cost.0 < old_cost.0 || (new_cost.0 == old_cost.0 && new_cost.1 < old_cost.1 - local_search::EPSILON)
+}
+
+// Savings algorithm of Clarke and Wright, serving customers i and j one after another instead of returning to the depot in between
+// saves d(i, depot) + d(depot, j) - d(i, j), the routes are merged in the order of decreasing savings as long as the capacity allows it,
+// in directed graphs the route ending in i can only be followed by the route starting in j
+fn clarke_wright(
+    adjacency_matrix: &DMatrix<f32>,
+    demands: &Vec<u32>,
+    depot: u32,
+    capacity: u32,
+    vehicles: u32,
+    directed: bool,
+) -> Vec<Vec<u32>> {
+    let vertex_count = adjacency_matrix.nrows() as u32;
+    let customers: Vec<u32> = (1..=vertex_count).filter(|vertex| *vertex != depot).collect();
+
+    let mut routes: Vec<Option<Vec<u32>>> = vec![None; vertex_count as usize];
+    let mut route_of = vec![0; vertex_count as usize];
+
+    for customer in &customers
+    {
+        routes[*customer as usize - 1] = Some(vec![*customer]);
+        route_of[*customer as usize - 1] = *customer as usize - 1;
+    }
+
+    let mut savings = Vec::new();
+
+    for i in &customers
+    {
+        for j in &customers
+        {
+            if i == j || (directed == false && i > j)
+            {
+                continue;
+            }
+
+            let saving = distance(adjacency_matrix, *i, depot) + distance(adjacency_matrix, depot, *j) - distance(adjacency_matrix, *i, *j);
+
+            // Forbidden edges never produce a saving
+            if saving.is_finite()
+            {
+                savings.push((saving, *i, *j));
+            }
+        }
+    }
+
+    savings.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    for (_, i, j) in savings
+    {
+        let route_i = route_of[i as usize - 1];
+        let route_j = route_of[j as usize - 1];
+
+        if route_i == route_j
+        {
+            continue;
+        }
+
+        let mut first = routes[route_i].clone().unwrap();
+        let mut second = routes[route_j].clone().unwrap();
+
+        if route_load(&first, demands) + route_load(&second, demands) > capacity
+        {
+            continue;
+        }
+
+        // i has to end the first route and j has to start the second one, undirected routes can be walked backwards
+        if first[first.len() - 1] != i && directed == false && first[0] == i
+        {
+            first.reverse();
+        }
+
+        if second[0] != j && directed == false && second[second.len() - 1] == j
+        {
+            second.reverse();
+        }
+
+        if first[first.len() - 1] != i || second[0] != j
+        {
+            continue;
+        }
+
+        for vertex in &second
+        {
+            route_of[*vertex as usize - 1] = route_i;
+        }
+
+        first.extend(second);
+        routes[route_i] = Some(first);
+        routes[route_j] = None;
+    }
+
+    let mut routes: Vec<Vec<u32>> = routes.into_iter().flatten().collect();
+
+    merge_surplus_routes(&mut routes, adjacency_matrix, depot, vehicles);
+
+    return routes;
+}
+
+// Sweep algorithm of Gillett and Miller, the customers are sorted by their angle around the depot and every vehicle takes the next customers
+// until its capacity is reached, every customer is tried as the first one and the shortest solution is kept and reordered
+fn sweep(
+    adjacency_matrix: &DMatrix<f32>,
+    vertex_list: &graph::VertexList,
+    depot: u32,
+    capacity: u32,
+    vehicles: u32,
+    directed: bool,
+) -> Vec<Vec<u32>> {
+    let (_, depot_y, depot_x) = vertex_list.vector[depot as usize - 1];
+
+    let mut customers: Vec<(f32, u32)> = vertex_list.vector.iter()
+        .filter(|(vertex, _, _)| *vertex != depot)
+        .map(|(vertex, y, x)| ((y - depot_y).atan2(x - depot_x), *vertex))
+        .collect();
+
+    customers.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let demands = &vertex_list.demands;
+    let mut best_routes = Vec::new();
+    let mut best_cost = (u32::MAX, f32::INFINITY);
+
+    for start in 0..customers.len()
+    {
+        let mut routes: Vec<Vec<u32>> = vec![Vec::new()];
+        let mut load = 0;
+
+        for k in 0..customers.len()
+        {
+            let customer = customers[(start + k) % customers.len()].1;
+            let demand = demands[customer as usize - 1];
+
+            if load + demand > capacity && routes[routes.len() - 1].len() > 0
+            {
+                routes.push(Vec::new());
+                load = 0;
+            }
+
+            let last = routes.len() - 1;
+            routes[last].push(customer);
+            load += demand;
+        }
+
+        merge_surplus_routes(&mut routes, adjacency_matrix, depot, vehicles);
+
+        let cost = routes.iter()
+            .map(|route| route_cost(route, adjacency_matrix, demands, depot, capacity))
+            .fold((0, 0.0), add_costs);
+
+        if is_better(cost, best_cost)
+        {
+            best_cost = cost;
+            best_routes = routes;
+        }
+    }
+
+    // Only the routes of the best start are reordered, improve_routes reorders them again when the route local search is enabled
+    for route in best_routes.iter_mut()
+    {
+        improve_route(route, adjacency_matrix, depot, directed);
+    }
+
+    return best_routes;
+}
+
+// When there are more routes than vehicles, the pair of routes whose concatenation adds the least length is merged until the vehicles suffice,
+// the merged routes ignore the capacity
+fn merge_surplus_routes(
+    routes: &mut Vec<Vec<u32>>,
+    adjacency_matrix: &DMatrix<f32>,
+    depot: u32,
+    vehicles: u32,
+) {
+    while vehicles > 0 && routes.len() > vehicles as usize
+    {
+        let mut best_merge = (0, 1, f32::INFINITY);
+
+        for a in 0..routes.len()
+        {
+            for b in 0..routes.len()
+            {
+                if a == b
+                {
+                    continue;
+                }
+
+                let last = routes[a][routes[a].len() - 1];
+                let first = routes[b][0];
+                let added_length = distance(adjacency_matrix, last, first) - distance(adjacency_matrix, last, depot) - distance(adjacency_matrix, depot, first);
+
+                if added_length < best_merge.2
+                {
+                    best_merge = (a, b, added_length);
+                }
+            }
+        }
+
+        let (a, b, _) = best_merge;
+        let second = routes[b].clone();
+
+        routes[a].extend(second);
+        routes.remove(b);
+    }
+}
+
+// Reorders the customers of a single route with 2-opt and Or-opt, the route is solved as a small tour through the depot
+pub fn improve_route(
+    route: &mut Vec<u32>,
+    adjacency_matrix: &DMatrix<f32>,
+    depot: u32,
+    directed: bool,
+) {
+    if route.len() < 3
+    {
+        return;
+    }
+
+    let vertices: Vec<u32> = std::iter::once(depot).chain(route.iter().copied()).collect();
+    let size = vertices.len();
+
+    let route_matrix = DMatrix::from_fn(size, size, |i, j| distance(adjacency_matrix, vertices[i], vertices[j]));
+
+    // Every vertex of the route is a candidate, sorted by distance like the candidate lists of the ants
+    let candidate_lists: Vec<Vec<u32>> = (0..size)
+        .map(|i| {
+            let mut neighbours: Vec<u32> = (1..=size as u32).filter(|j| *j as usize != i + 1).collect();
+            neighbours.sort_by(|a, b| route_matrix[(i, *a as usize - 1)].total_cmp(&route_matrix[(i, *b as usize - 1)]));
+            neighbours
+        })
+        .collect();
+
+    let mut tour: Vec<u32> = (1..=size as u32).collect();
+
+    local_search::improve_tour(&mut tour, local_search::LocalSearch::TwoOpt, &route_matrix, &candidate_lists, directed);
+    local_search::improve_tour(&mut tour, local_search::LocalSearch::OrOpt, &route_matrix, &candidate_lists, directed);
+
+    let depot_position = tour.iter().position(|vertex| *vertex == 1).unwrap();
+    tour.rotate_left(depot_position);
+
+    *route = tour[1..].iter().map(|vertex| vertices[*vertex as usize - 1]).collect();
+}
+
+// Route-based local search, customers are moved to other routes and exchanged between routes, and every route is reordered,
+// a move is accepted when it lowers the demand exceeding the capacity or keeps it and shortens the routes
+fn improve_routes(
+    routes: &mut Vec<Vec<u32>>,
+    adjacency_matrix: &DMatrix<f32>,
+    demands: &Vec<u32>,
+    depot: u32,
+    capacity: u32,
+    directed: bool,
+) {
+    loop
+    {
+        for route in routes.iter_mut()
+        {
+            improve_route(route, adjacency_matrix, depot, directed);
+        }
+
+        let relocated = relocate_customers(routes, adjacency_matrix, demands, depot, capacity);
+        let exchanged = exchange_customers(routes, adjacency_matrix, demands, depot, capacity);
+
+        if !relocated && !exchanged
+        {
+            break;
+        }
+    }
+
+    // A route that lost all of its customers does not need a vehicle
+    routes.retain(|route| !route.is_empty());
+}
+
+// Change of the route length when the customer at index i is left out, its neighbours are connected directly
+fn removal_delta(
+    route: &[u32],
+    i: usize,
+    adjacency_matrix: &DMatrix<f32>,
+    depot: u32,
+) -> f32 {
+    // An empty route does not leave the depot at all
+    if route.len() == 1
+    {
+        return -route_length(route, adjacency_matrix, depot);
+    }
+
+    let previous = if i == 0 { depot } else { route[i - 1] };
+    let next = if i + 1 == route.len() { depot } else { route[i + 1] };
+
+    distance(adjacency_matrix, previous, next) - distance(adjacency_matrix, previous, route[i]) - distance(adjacency_matrix, route[i], next)
+}
+
+// Change of the route length when the customer is inserted before index position
+fn insertion_delta(
+    route: &[u32],
+    position: usize,
+    customer: u32,
+    adjacency_matrix: &DMatrix<f32>,
+    depot: u32,
+) -> f32 {
+    if route.is_empty()
+    {
+        return distance(adjacency_matrix, depot, customer) + distance(adjacency_matrix, customer, depot);
+    }
+
+    let previous = if position == 0 { depot } else { route[position - 1] };
+    let next = if position == route.len() { depot } else { route[position] };
+
+    distance(adjacency_matrix, previous, customer) + distance(adjacency_matrix, customer, next) - distance(adjacency_matrix, previous, next)
+}
+
+// Change of the route length when the customer at index i is replaced by another customer
+fn replacement_delta(
+    route: &[u32],
+    i: usize,
+    customer: u32,
+    adjacency_matrix: &DMatrix<f32>,
+    depot: u32,
+) -> f32 {
+    let previous = if i == 0 { depot } else { route[i - 1] };
+    let next = if i + 1 == route.len() { depot } else { route[i + 1] };
+
+    distance(adjacency_matrix, previous, customer) + distance(adjacency_matrix, customer, next)
+        - distance(adjacency_matrix, previous, route[i]) - distance(adjacency_matrix, route[i], next)
+}
+
+// A route over a forbidden edge is infinitely long, so the length after a move that removes such an edge can not be derived from the delta,
+// only then the changed route is measured in full
+fn changed_length(
+    length: f32,
+    delta: f32,
+    changed_route: impl FnOnce() -> Vec<u32>,
+    adjacency_matrix: &DMatrix<f32>,
+    depot: u32,
+) -> f32 {
+    let changed_length = length + delta;
+
+    if changed_length.is_nan()
+    {
+        return route_length(&changed_route(), adjacency_matrix, depot);
+    }
+
+    return changed_length;
+}
+
+// One pass of the relocate move, every customer is inserted into the best position of another route, returns whether the routes improved,
+// the moves are rated by the change they make to the loads and lengths of both routes
+fn relocate_customers(
+    routes: &mut Vec<Vec<u32>>,
+    adjacency_matrix: &DMatrix<f32>,
+    demands: &Vec<u32>,
+    depot: u32,
+    capacity: u32,
+) -> bool {
+    let mut loads: Vec<u32> = routes.iter().map(|route| route_load(route, demands)).collect();
+    let mut lengths: Vec<f32> = routes.iter().map(|route| route_length(route, adjacency_matrix, depot)).collect();
+    let mut improved = false;
+
+    for a in 0..routes.len()
+    {
+        let mut i = 0;
+
+        while i < routes[a].len()
+        {
+            let customer = routes[a][i];
+            let demand = demands[customer as usize - 1];
+
+            let shortened_load = loads[a] - demand;
+            let shortened_length = changed_length(
+                lengths[a],
+                removal_delta(&routes[a], i, adjacency_matrix, depot),
+                || routes[a].iter().enumerate().filter(|(k, _)| *k != i).map(|(_, vertex)| *vertex).collect(),
+                adjacency_matrix,
+                depot,
+            );
+
+            let mut best_move = None;
+
+            for b in 0..routes.len()
+            {
+                if a == b
+                {
+                    continue;
+                }
+
+                let old_cost = (loads[a].saturating_sub(capacity) + loads[b].saturating_sub(capacity), lengths[a] + lengths[b]);
+                let extended_excess = (loads[b] + demand).saturating_sub(capacity);
+
+                for position in 0..=routes[b].len()
+                {
+                    let extended_length = changed_length(
+                        lengths[b],
+                        insertion_delta(&routes[b], position, customer, adjacency_matrix, depot),
+                        || {
+                            let mut extended_route = routes[b].clone();
+                            extended_route.insert(position, customer);
+                            extended_route
+                        },
+                        adjacency_matrix,
+                        depot,
+                    );
+
+                    let new_cost = (shortened_load.saturating_sub(capacity) + extended_excess, shortened_length + extended_length);
+
+                    if is_better(new_cost, old_cost)
+                    {
+                        best_move = Some((b, position));
+                        break;
+                    }
+                }
+
+                if best_move.is_some()
+                {
+                    break;
+                }
+            }
+
+            if let Some((b, position)) = best_move
+            {
+                routes[a].remove(i);
+                routes[b].insert(position, customer);
+
+                for route in [a, b]
+                {
+                    loads[route] = route_load(&routes[route], demands);
+                    lengths[route] = route_length(&routes[route], adjacency_matrix, depot);
+                }
+
+                improved = true;
+            } else {
+                i += 1;
+            }
+        }
+    }
+
+    return improved;
+}
+
+// One pass of the exchange move, two customers of different routes swap their places, returns whether the routes improved
+fn exchange_customers(
+    routes: &mut Vec<Vec<u32>>,
+    adjacency_matrix: &DMatrix<f32>,
+    demands: &Vec<u32>,
+    depot: u32,
+    capacity: u32,
+) -> bool {
+    let mut loads: Vec<u32> = routes.iter().map(|route| route_load(route, demands)).collect();
+    let mut lengths: Vec<f32> = routes.iter().map(|route| route_length(route, adjacency_matrix, depot)).collect();
+    let mut improved = false;
+
+    for a in 0..routes.len()
+    {
+        for b in a + 1..routes.len()
+        {
+            for i in 0..routes[a].len()
+            {
+                for j in 0..routes[b].len()
+                {
+                    let (customer_a, customer_b) = (routes[a][i], routes[b][j]);
+                    let (demand_a, demand_b) = (demands[customer_a as usize - 1], demands[customer_b as usize - 1]);
+
+                    let swapped_route = |route: &Vec<u32>, index: usize, customer: u32| {
+                        let mut swapped_route = route.clone();
+                        swapped_route[index] = customer;
+                        swapped_route
+                    };
+
+                    let old_cost = (loads[a].saturating_sub(capacity) + loads[b].saturating_sub(capacity), lengths[a] + lengths[b]);
+                    let new_excess = (loads[a] - demand_a + demand_b).saturating_sub(capacity) + (loads[b] - demand_b + demand_a).saturating_sub(capacity);
+                    let new_length = changed_length(
+                        lengths[a],
+                        replacement_delta(&routes[a], i, customer_b, adjacency_matrix, depot),
+                        || swapped_route(&routes[a], i, customer_b),
+                        adjacency_matrix,
+                        depot,
+                    ) + changed_length(
+                        lengths[b],
+                        replacement_delta(&routes[b], j, customer_a, adjacency_matrix, depot),
+                        || swapped_route(&routes[b], j, customer_a),
+                        adjacency_matrix,
+                        depot,
+                    );
+
+                    if is_better((new_excess, new_length), old_cost)
+                    {
+                        routes[a][i] = customer_b;
+                        routes[b][j] = customer_a;
+
+                        for route in [a, b]
+                        {
+                            loads[route] = route_load(&routes[route], demands);
+                            lengths[route] = route_length(&routes[route], adjacency_matrix, depot);
+                        }
+
+                        improved = true;
+                    }
+                }
+            }
+        }
+    }
+
+    return improved;
+}
+
+#[derive(Component)]
+struct DemandLabel;
+
+// This system draws the demand of every vertex, marks the routes exceeding the capacity of a vehicle in red and lists the loads of the vehicles
+pub fn draw_vehicle_routing(
+    mut commands: Commands,
+    mut lines: ResMut<DebugLines>,
+    asset_server: Res<AssetServer>,
+    window: Query<&mut Window>,
+    vertex_list: Res<graph::VertexList>,
+    shortest_cycle: Res<graph::ShortestCycle>,
+    vehicle_routing: Res<VehicleRouting>,
+    mut ant_colony_stats_text: Query<&mut Text, With<app::AntColonyStatsText>>,
+) {
+    let win = window.single();
+
+    commands.despawn_all::<With<DemandLabel>>();
+
+    // Demands are only shown once they matter, either a vehicle routing solution exists or some vertex has a demand other than 1
+    if vehicle_routing.routes.len() > 0 || vertex_list.demands.iter().any(|demand| *demand != 1)
+    {
+        for (vertex, y, x) in &vertex_list.vector
+        {
+            if *vertex == vehicle_routing.depot && vehicle_routing.routes.len() > 0
+            {
+                continue;
+            }
+
+            commands.spawn((
+                TextBundle::from_section(
+                    format!("[{}]", vertex_list.demands[*vertex as usize - 1]),
+                    TextStyle {
+                        font: asset_server.load("fonts/FiraSans-ExtraLight.ttf"),
+                        font_size: 20.0,
+                        color: Color::YELLOW,
+                    },
+                )
+                    .with_text_alignment(TextAlignment::Left)
+                    .with_style(Style {
+                        position_type: PositionType::Absolute,
+                        position: UiRect {
+                            bottom: Val::Px(y + (win.height() / 2.) - 10.),
+                            left: Val::Px(x + (win.width() / 2.) + 18.),
+                            ..default()
+                        },
+                        ..default()
+                    }),
+                DemandLabel,
+            ));
+        }
+    }
+
+    for route in vehicle_routing.routes.iter().filter(|route| route_load(route, &vertex_list.demands) > vehicle_routing.capacity)
+    {
+        let mut previous = vehicle_routing.depot;
+
+        for vertex in route.iter().copied().chain(std::iter::once(vehicle_routing.depot))
+        {
+            let (_, y1, x1) = vertex_list.vector[previous as usize - 1];
+            let (_, y2, x2) = vertex_list.vector[vertex as usize - 1];
+
+            graph::draw_thick_line(&mut lines, Vec3::new(x1, y1, 0.), Vec3::new(x2, y2, 0.), Color::RED, 3);
+
+            previous = vertex;
+        }
+    }
+
+    // The loads change together with the demands and the capacity, so the text is refreshed whenever one of them changes
+    if vehicle_routing.routes.len() > 0 && (vehicle_routing.is_changed() || vertex_list.is_changed())
+    {
+        let mut route_lines = Vec::new();
+        let mut overloaded_routes = 0;
+
+        for (index, route) in vehicle_routing.routes.iter().enumerate()
+        {
+            let load = route_load(route, &vertex_list.demands);
+
+            if load > vehicle_routing.capacity
+            {
+                overloaded_routes += 1;
+            }
+
+            route_lines.push(format!(
+                "Route {}: length {:.1}, load {}/{}{}",
+                index + 1,
+                shortest_cycle.routes.get(index).map_or(0.0, |route| route.1),
+                load,
+                vehicle_routing.capacity,
+                if load > vehicle_routing.capacity { " over capacity" } else { "" },
+            ));
+        }
+
+        let header = format!(
+            "{}: {} routes with total length {:.1}{}{}",
+            vehicle_routing.solver,
+            vehicle_routing.routes.len(),
+            shortest_cycle.total_cycle_weight,
+            if vehicle_routing.vehicles > 0 { format!(" for {} vehicles", vehicle_routing.vehicles) } else { String::new() },
+            if overloaded_routes > 0 { format!(", {} over capacity", overloaded_routes) } else { String::new() },
+        );
+
+        for mut text in &mut ant_colony_stats_text
+        {
+            text.sections[0].value = std::iter::once(header.clone()).chain(route_lines.iter().cloned()).collect::<Vec<String>>().join("\n");
+        }
+    }
+}
@@ -0,0 +1,144 @@
+use crate::graph;
+
+use bevy::{input::mouse::{MouseMotion, MouseWheel}, prelude::*};
+use bevy_prototype_debug_lines::*;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+// Distance in pixels between the camera and the projection plane, a larger value flattens the perspective
+const FOCAL_LENGTH: f32 = 1200.;
+
+// Half of the edge length of the cube the generated 3D instances fill
+const GENERATED_EXTENT: f32 = 400.;
+
+// This resource stores the camera that orbits around 3D instances, the canvas shows the perspective projection of their points
+#[derive(Resource)]
+pub struct OrbitCamera {
+    // Rotation around the vertical axis and elevation above the horizontal plane in radians, changed by dragging with the left mouse button
+    pub yaw: f32,
+    pub pitch: f32,
+    // Distance of the camera from the point it orbits, changed by the mouse wheel
+    pub distance: f32,
+    // Point the camera orbits and looks at, moved by dragging with the right mouse button
+    pub target: Vec3,
+}
+
+impl OrbitCamera {
+    fn position(&self) -> Vec3 {
+        self.target + self.distance * Vec3::new(self.pitch.cos() * self.yaw.sin(), self.pitch.sin(), self.pitch.cos() * self.yaw.cos())
+    }
+
+    // Forward, right and up direction of the camera
+    fn axes(&self) -> (Vec3, Vec3, Vec3) {
+        let forward = (self.target - self.position()).normalize();
+        let right = forward.cross(Vec3::Y).normalize();
+
+        (forward, right, right.cross(forward))
+    }
+
+    // Position of the point on the canvas, points behind the camera are pushed onto the projection plane
+    fn project(&self, point: Vec3) -> Vec2 {
+        let (forward, right, up) = self.axes();
+        let offset = point - self.position();
+        let depth = offset.dot(forward).max(1.);
+
+        Vec2::new(offset.dot(right), offset.dot(up)) * FOCAL_LENGTH / depth
+    }
+}
+
+// Replaces the vertices by count random points inside a cube centred on the origin, the vertices get the default values of every problem variant
+pub fn generate_instance(
+    vertex_list: &mut graph::VertexList,
+    count: u32,
+    seed: u64,
+) {
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    for _ in 0..count
+    {
+        let point = Vec3::new(
+            rng.gen_range(-GENERATED_EXTENT..=GENERATED_EXTENT),
+            rng.gen_range(-GENERATED_EXTENT..=GENERATED_EXTENT),
+            rng.gen_range(-GENERATED_EXTENT..=GENERATED_EXTENT),
+        );
+
+        // The canvas position is filled in by project_vertices
+        vertex_list.push_vertex(point.y, point.x, Some(point));
+    }
+}
+
+// This system turns mouse input into camera movement while a 3D instance is shown
+pub fn orbit_camera(
+    mouse_button_input: Res<Input<MouseButton>>,
+    mut mouse_motion_events: EventReader<MouseMotion>,
+    mut mouse_wheel_events: EventReader<MouseWheel>,
+    vertex_list: Res<graph::VertexList>,
+    mut orbit_camera: ResMut<OrbitCamera>,
+) {
+    let motion: Vec2 = mouse_motion_events.iter().map(|event| event.delta).sum();
+    let scroll: f32 = mouse_wheel_events.iter().map(|event| event.y).sum();
+
+    if vertex_list.points.is_empty()
+    {
+        return;
+    }
+
+    if motion != Vec2::ZERO && mouse_button_input.pressed(MouseButton::Left)
+    {
+        orbit_camera.yaw -= motion.x * 0.005;
+        orbit_camera.pitch = (orbit_camera.pitch + motion.y * 0.005).clamp(-1.5, 1.5);
+    }
+
+    // The target follows the cursor, so the scene moves by the same amount of pixels in the plane of the target
+    if motion != Vec2::ZERO && mouse_button_input.pressed(MouseButton::Right)
+    {
+        let (_, right, up) = orbit_camera.axes();
+        let scale = orbit_camera.distance / FOCAL_LENGTH;
+
+        orbit_camera.target += (up * motion.y - right * motion.x) * scale;
+    }
+
+    if scroll != 0.0
+    {
+        orbit_camera.distance = (orbit_camera.distance * 0.9_f32.powf(scroll)).clamp(100., 20000.);
+    }
+}
+
+// This system projects the points of a 3D instance onto the canvas positions of the vertices, which every other system draws,
+// vertices placed on the canvas have no points and are left untouched
+pub fn project_vertices(
+    orbit_camera: Res<OrbitCamera>,
+    mut vertex_list: ResMut<graph::VertexList>,
+) {
+    if vertex_list.points.is_empty() || vertex_list.points.len() != vertex_list.vector.len()
+    {
+        return;
+    }
+
+    if orbit_camera.is_changed() || vertex_list.is_changed()
+    {
+        for i in 0..vertex_list.points.len()
+        {
+            let position = orbit_camera.project(vertex_list.points[i]);
+            vertex_list.vector[i] = (i as u32 + 1, position.y, position.x);
+        }
+    }
+}
+
+// This system draws the coordinate axes of a 3D instance, x in red, y in green and z in blue, so that the orientation stays visible
+pub fn draw_axes(
+    mut lines: ResMut<DebugLines>,
+    vertex_list: Res<graph::VertexList>,
+    orbit_camera: Res<OrbitCamera>,
+) {
+    if vertex_list.points.is_empty()
+    {
+        return;
+    }
+
+    let origin = orbit_camera.project(Vec3::ZERO).extend(0.);
+
+    for (axis, color) in [(Vec3::X, Color::RED), (Vec3::Y, Color::GREEN), (Vec3::Z, Color::BLUE)]
+    {
+        lines.line_colored(origin, orbit_camera.project(axis * GENERATED_EXTENT).extend(0.), 0.0, color);
+    }
+}